@@ -1,6 +1,8 @@
 #[derive(Debug)]
 pub enum ProleError {
     Exit(String),
+    /// A requested key (e.g. a contig or genome ID) was not present.
+    NotFound(String),
     IoError(std::io::Error),
     ParseFloatError(std::num::ParseFloatError),
     ParseIntError(std::num::ParseIntError),
@@ -11,6 +13,7 @@ impl std::fmt::Display for ProleError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::Exit(e) => write!(f, "{}", e),
+            Self::NotFound(e) => write!(f, "Not found: {}", e),
             Self::IoError(e) => write!(f, "IO error: {}", e),
             Self::ParseFloatError(e) => write!(f, "Parse error: {}", e),
             Self::ParseIntError(e) => write!(f, "Parse error: {}", e),
@@ -23,6 +26,7 @@ impl std::error::Error for ProleError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::Exit(_) => None,
+            Self::NotFound(_) => None,
             Self::IoError(e) => Some(e),
             Self::ParseFloatError(e) => Some(e),
             Self::ParseIntError(e) => Some(e),
@@ -1,17 +1,132 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
+use bio::alphabets::dna::revcomp;
 use bio::io::fasta;
 use flate2::read::GzDecoder;
+use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 use crate::error::{ProleError, ProleResult};
+use crate::genome::prodigal_gff::ProdigalGff;
+use crate::sequence::composition::BaseCounts;
+use crate::sequence::kmer::KmerIndex;
+use crate::sequence::tln_table::TranslationTable;
+use crate::sequence::translate::best_frame;
+use crate::util::io::{FileWriter, open_reader};
 
-/// A genome fasta formatted file. Sequence IDs must be unique.
-pub struct FastaFile(pub HashMap<String, (Option<String>, String)>);
+/// The action to take when merging two [FastaFile]s that contain the same sequence ID.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ConflictPolicy {
+    /// Return an error, leaving the original [FastaFile] unchanged.
+    Error,
+    /// Keep the existing record.
+    KeepSelf,
+    /// Replace the existing record with the incoming one.
+    Overwrite,
+}
+
+/// Options controlling how a [FastaFile] is parsed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FastaReadOptions {
+    /// Remove a single trailing `*` (stop codon) from each sequence.
+    pub strip_trailing_stop: bool,
+}
+
+impl Default for FastaReadOptions {
+    fn default() -> Self {
+        Self { strip_trailing_stop: true }
+    }
+}
+
+/// The position of the first record with each lowercased ID, and the number of records it was
+/// built from. Built on first use by [FastaFile::get_sequence_ci].
+#[derive(Debug, Clone, Default)]
+struct LowercaseIndex(OnceCell<(usize, HashMap<String, usize>)>);
+
+/// A genome fasta formatted file. Sequence IDs must be unique, and records are kept in the
+/// order they were read (or inserted).
+#[derive(Debug, Clone, Default)]
+pub struct FastaFile(pub IndexMap<String, (Option<String>, String)>, LowercaseIndex);
+
+impl From<IndexMap<String, (Option<String>, String)>> for FastaFile {
+    fn from(records: IndexMap<String, (Option<String>, String)>) -> Self {
+        Self::from_map(records)
+    }
+}
+
+impl FromIterator<(String, (Option<String>, String))> for FastaFile {
+    /// Create a [FastaFile] from `(id, (description, sequence))` records, a record replaces any
+    /// earlier record with the same ID (see [FastaFile::insert]).
+    fn from_iter<I: IntoIterator<Item=(String, (Option<String>, String))>>(iter: I) -> Self {
+        Self::from_map(iter.into_iter().collect())
+    }
+}
 
 impl FastaFile {
+    /// Create an empty [FastaFile].
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let mut fasta = FastaFile::new();
+    /// fasta.insert("a".to_string(), None, "ACGT".to_string());
+    /// assert_eq!(fasta.get_sequence("a"), Some("ACGT"));
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn from_map(records: IndexMap<String, (Option<String>, String)>) -> Self {
+        Self(records, LowercaseIndex::default())
+    }
+
+    /// Insert a record, returning the description and sequence of the record it replaced (if any).
+    /// A replaced record keeps its position, otherwise the record is added to the end.
+    pub fn insert(&mut self, id: String, desc: Option<String>, seq: String) -> Option<(Option<String>, String)> {
+        self.1.0.take();
+        self.0.insert(id, (desc, seq))
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an [Iterator] over the sequence IDs, in order.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_reader(">b\nAC\n>a\nGT\n".as_bytes()).unwrap();
+    /// assert_eq!(fasta.ids().collect::<Vec<_>>(), vec!["b", "a"]);
+    /// ```
+    pub fn ids(&self) -> impl Iterator<Item=&str> {
+        self.0.keys().map(|id| id.as_str())
+    }
+
+    /// Returns an [Iterator] over the `(id, description, sequence)` of each record, in order.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_reader(">a desc\nACGT\n".as_bytes()).unwrap();
+    /// assert_eq!(fasta.iter().collect::<Vec<_>>(), vec![("a", Some("desc"), "ACGT")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item=(&str, Option<&str>, &str)> {
+        self.0.iter().map(|(id, (desc, seq))| (id.as_str(), desc.as_deref(), seq.as_str()))
+    }
+
     /// Read the content from a [BufReader] and parse it into a [FastaFile].
     ///
     /// ```no_run
@@ -23,8 +138,36 @@ impl FastaFile {
     /// let file = FastaFile::from_bufreader(reader).unwrap();
     /// ```
     pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        Self::from_bufreader_with(buf, FastaReadOptions::default())
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [FastaFile], the reader
+    /// is buffered internally.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let file = FastaFile::from_reader(">a\nACGT\n".as_bytes()).unwrap();
+    /// assert_eq!(file.get_sequence("a"), Some("ACGT"));
+    /// ```
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
+    }
+
+    /// Read the content from a [BufReader] and parse it into a [FastaFile] using the
+    /// specified [FastaReadOptions].
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::genome::fasta_file::{FastaFile, FastaReadOptions};
+    ///
+    /// let options = FastaReadOptions { strip_trailing_stop: false };
+    /// let file = FastaFile::from_bufreader_with(BufReader::new(">a\nMK*\n".as_bytes()), options).unwrap();
+    /// assert_eq!(file.get_sequence("a"), Some("MK*"));
+    /// ```
+    pub fn from_bufreader_with<T: std::io::Read>(buf: BufReader<T>, options: FastaReadOptions) -> ProleResult<Self> {
         let mut reader = fasta::Reader::new(buf).records();
-        let mut out = HashMap::new();
+        let mut out = IndexMap::new();
         while let Some(Ok(record)) = reader.next() {
             let id = record.id().to_string();
             if out.contains_key(&id) {
@@ -32,13 +175,13 @@ impl FastaFile {
             }
             let desc = record.desc().map(|x| x.to_string());
             let mut seq = String::from_utf8(record.seq().to_vec()).map_err(ProleError::Utf8Error)?;
-            if seq.ends_with("*") {
+            if options.strip_trailing_stop && seq.ends_with('*') {
                 seq = seq[..seq.len() - 1].to_string();
             }
             out.insert(id, (desc, seq));
         }
 
-        Ok(Self(out))
+        Ok(Self::from_map(out))
     }
 
     /// Read the content from a [Path] and parse it into a [FastaFile].
@@ -72,6 +215,31 @@ impl FastaFile {
         Self::from_bufreader(reader)
     }
 
+    /// Count the number of records in the FASTA file at [Path] without storing their sequences,
+    /// compressed files are decompressed automatically (see [open_reader]).
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let n_contigs = FastaFile::count_records(Path::new("tests/data/contigs.fa.gz")).unwrap();
+    /// ```
+    pub fn count_records(path: &Path) -> ProleResult<usize> {
+        let mut reader = open_reader(path)?;
+        let mut line = Vec::new();
+        let mut count = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line).map_err(ProleError::IoError)? == 0 {
+                break;
+            }
+            if line.first() == Some(&b'>') {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     /// Return the length of the sequence stored at `contig`.
     pub fn get_length(&self, contig: &str) -> Option<usize> {
         if let Some(seq) = self.get_sequence(contig) {
@@ -89,6 +257,159 @@ impl FastaFile {
         None
     }
 
+    /// Return the sequence stored at `contig`, or [ProleError::NotFound] if it is missing.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("a".to_string(), (None, "ACGT".to_string()))]);
+    /// assert_eq!(fasta.get_sequence_or_err("a").unwrap(), "ACGT");
+    /// assert_eq!(fasta.get_sequence_or_err("b").unwrap_err().to_string(), "Not found: contig b");
+    /// ```
+    pub fn get_sequence_or_err(&self, contig: &str) -> ProleResult<&str> {
+        self.get_sequence(contig).ok_or_else(|| ProleError::NotFound(format!("contig {}", contig)))
+    }
+
+    /// Return the ID and length of the longest sequence, or [None] if there are no records.
+    /// Ties are resolved by taking the first record in input order.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([
+    ///     ("a".to_string(), (None, "ACGT".to_string())),
+    ///     ("b".to_string(), (None, "ACGTAC".to_string())),
+    /// ]);
+    /// assert_eq!(fasta.longest(), Some(("b", 6)));
+    /// ```
+    pub fn longest(&self) -> Option<(&str, usize)> {
+        self.0.iter()
+            .rev()
+            .map(|(id, (_desc, seq))| (id.as_str(), seq.len()))
+            .max_by_key(|(_id, len)| *len)
+    }
+
+    /// Return the ID and length of the shortest sequence, or [None] if there are no records.
+    /// Ties are resolved by taking the first record in input order.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([
+    ///     ("a".to_string(), (None, "ACGT".to_string())),
+    ///     ("b".to_string(), (None, "ACGTAC".to_string())),
+    /// ]);
+    /// assert_eq!(fasta.shortest(), Some(("a", 4)));
+    /// ```
+    pub fn shortest(&self) -> Option<(&str, usize)> {
+        self.0.iter()
+            .map(|(id, (_desc, seq))| (id.as_str(), seq.len()))
+            .min_by_key(|(_id, len)| *len)
+    }
+
+    /// Iterate over the `(id, sequence)` pairs in descending order of sequence length,
+    /// ties are ordered by ID.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([
+    ///     ("a".to_string(), (None, "ACGT".to_string())),
+    ///     ("b".to_string(), (None, "ACGTAC".to_string())),
+    /// ]);
+    /// let ids: Vec<&str> = fasta.iter_by_length_desc().map(|(id, _seq)| id).collect();
+    /// assert_eq!(ids, vec!["b", "a"]);
+    /// ```
+    pub fn iter_by_length_desc(&self) -> impl Iterator<Item=(&str, &str)> {
+        let mut records: Vec<(&str, &str)> = self.0.iter().map(|(id, (_desc, seq))| (id.as_str(), seq.as_str())).collect();
+        records.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+        records.into_iter()
+    }
+
+    /// Iterate over the `(id, sequence)` pairs in ascending order of sequence length,
+    /// ties are ordered by ID.
+    pub fn iter_by_length_asc(&self) -> impl Iterator<Item=(&str, &str)> {
+        let mut records: Vec<(&str, &str)> = self.0.iter().map(|(id, (_desc, seq))| (id.as_str(), seq.as_str())).collect();
+        records.sort_by(|a, b| a.1.len().cmp(&b.1.len()).then_with(|| a.0.cmp(b.0)));
+        records.into_iter()
+    }
+
+    /// Return the sequence stored at `contig`, ignoring ASCII case when matching the ID. If more
+    /// than one ID matches, the first in input order is used.
+    ///
+    /// An exact match is tried first, otherwise the lowercased ID is looked up in an index that
+    /// is built on the first case-insensitive lookup (and rebuilt if the records are changed by
+    /// a method of [FastaFile]). If the records have been changed through `.0` since the index
+    /// was built, a changed record count or a stale entry falls back to a linear scan of the IDs.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("Contig_1".to_string(), (None, "ACGT".to_string()))]);
+    /// assert_eq!(fasta.get_sequence_ci("contig_1"), Some("ACGT"));
+    /// assert_eq!(fasta.get_sequence("contig_1"), None);
+    /// ```
+    pub fn get_sequence_ci(&self, contig: &str) -> Option<&str> {
+        if let Some(seq) = self.get_sequence(contig) {
+            return Some(seq);
+        }
+        let (n_records, index) = self.1.0.get_or_init(|| {
+            let mut out = HashMap::with_capacity(self.0.len());
+            for (i, id) in self.0.keys().enumerate() {
+                out.entry(id.to_ascii_lowercase()).or_insert(i);
+            }
+            (self.0.len(), out)
+        });
+        let linear_scan = || self.0.iter()
+            .find(|(id, _)| id.eq_ignore_ascii_case(contig))
+            .map(|(_, (_desc, seq))| seq.as_str());
+        if *n_records != self.0.len() {
+            return linear_scan();
+        }
+        match index.get(&contig.to_ascii_lowercase()).map(|&i| self.0.get_index(i)) {
+            Some(Some((id, (_desc, seq)))) if id.eq_ignore_ascii_case(contig) => Some(seq.as_str()),
+            Some(_) => linear_scan(),
+            None => None,
+        }
+    }
+
+    /// Returns a map of each lowercased ID to the original ID, allowing constant time
+    /// case-insensitive lookups. If more than one ID has the same lowercase form, the first in
+    /// input order is used.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("Contig_1".to_string(), (None, "ACGT".to_string()))]);
+    /// let index = fasta.lowercase_index();
+    /// assert_eq!(fasta.get_sequence(index["contig_1"]), Some("ACGT"));
+    /// ```
+    pub fn lowercase_index(&self) -> HashMap<String, &str> {
+        let mut out = HashMap::with_capacity(self.0.len());
+        for id in self.0.keys() {
+            out.entry(id.to_ascii_lowercase()).or_insert(id.as_str());
+        }
+        out
+    }
+
+    /// Return the sequences stored at each of `contigs`, in the same order as requested.
+    /// Contigs that are not present are returned as [None].
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("a".to_string(), (None, "ACGT".to_string()))]);
+    /// assert_eq!(fasta.get_sequences(&["a", "b"]), vec![Some("ACGT"), None]);
+    /// ```
+    pub fn get_sequences<'a>(&'a self, contigs: &[&str]) -> Vec<Option<&'a str>> {
+        contigs.iter().map(|contig| self.get_sequence(contig)).collect()
+    }
+
+    /// Returns true if a record exists for `contig`.
+    pub fn contains(&self, contig: &str) -> bool {
+        self.0.contains_key(contig)
+    }
+
     /// Return the description stored at `contig`.
     pub fn get_description(&self, config: &str) -> Option<&str> {
         if let Some((desc, _seq)) = self.0.get(config) {
@@ -96,6 +417,476 @@ impl FastaFile {
         }
         None
     }
+
+    /// Returns an error naming the first record (in the order they were read) that has an empty
+    /// sequence, e.g. a header with no sequence lines. Empty records are accepted when parsing,
+    /// so this check must be called explicitly.
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_bufreader(BufReader::new(">a\nACGT\n>b\n".as_bytes())).unwrap();
+    /// assert!(fasta.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> ProleResult<()> {
+        match self.0.iter().find(|(_id, (_desc, seq))| seq.is_empty()) {
+            Some((id, _)) => Err(ProleError::Exit(format!("Empty sequence for: {}", id))),
+            None => Ok(()),
+        }
+    }
+
+    /// Merge the records from `other` into this [FastaFile], using `on_conflict` to resolve
+    /// records present in both.
+    ///
+    /// If the policy is [ConflictPolicy::Error], the error names the first colliding ID (in the
+    /// order of `other`) and no records are merged.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::{ConflictPolicy, FastaFile};
+    ///
+    /// let mut a = FastaFile::from_iter([("a".to_string(), (None, "ACGT".to_string()))]);
+    /// let b = FastaFile::from_iter([("b".to_string(), (None, "TTTT".to_string()))]);
+    /// a.merge(b, ConflictPolicy::Error).unwrap();
+    /// assert_eq!(a.len(), 2);
+    /// ```
+    pub fn merge(&mut self, other: FastaFile, on_conflict: ConflictPolicy) -> ProleResult<()> {
+        self.1.0.take();
+        match on_conflict {
+            ConflictPolicy::Error => {
+                if let Some(id) = other.0.keys().find(|id| self.0.contains_key(*id)) {
+                    return Err(ProleError::Exit(format!("Duplicate ID found when merging: {}", id)));
+                }
+                self.0.extend(other.0);
+            }
+            ConflictPolicy::KeepSelf => {
+                for (id, record) in other.0 {
+                    self.0.entry(id).or_insert(record);
+                }
+            }
+            ConflictPolicy::Overwrite => self.0.extend(other.0),
+        }
+        Ok(())
+    }
+
+    /// Returns a new [FastaFile] with each sequence ID transformed by `f`, descriptions and
+    /// sequences are unchanged. An error is returned if two IDs are transformed to the same value.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("contig1".to_string(), (None, "ACGT".to_string()))]);
+    /// let renamed = fasta.rename(|id| format!("G000123_{}", id)).unwrap();
+    /// assert_eq!(renamed.get_sequence("G000123_contig1"), Some("ACGT"));
+    /// ```
+    pub fn rename<F: Fn(&str) -> String>(&self, f: F) -> ProleResult<FastaFile> {
+        let mut out = IndexMap::with_capacity(self.0.len());
+        for (id, record) in &self.0 {
+            let new_id = f(id);
+            if out.contains_key(&new_id) {
+                return Err(ProleError::Exit(format!("Duplicate ID found when renaming: {}", new_id)));
+            }
+            out.insert(new_id, record.clone());
+        }
+        Ok(FastaFile::from_map(out))
+    }
+
+    /// Remove records whose sequence (case-insensitive) is identical to that of an earlier record,
+    /// the first record in input order is kept. Returns the `(removed_id, kept_id)` pairs.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let mut fasta = FastaFile::from_iter([
+    ///     ("a".to_string(), (None, "ACGT".to_string())),
+    ///     ("b".to_string(), (None, "acgt".to_string())),
+    /// ]);
+    /// assert_eq!(fasta.deduplicate_by_sequence(), vec![("b".to_string(), "a".to_string())]);
+    /// ```
+    pub fn deduplicate_by_sequence(&mut self) -> Vec<(String, String)> {
+        self.1.0.take();
+        let mut seen: HashMap<String, String> = HashMap::with_capacity(self.0.len());
+        let mut removed = Vec::new();
+        self.0.retain(|id, (_desc, seq)| {
+            match seen.entry(seq.to_ascii_uppercase()) {
+                Entry::Occupied(kept) => {
+                    removed.push((id.clone(), kept.get().clone()));
+                    false
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(id.clone());
+                    true
+                }
+            }
+        });
+        removed
+    }
+
+    /// Partition the records into `n` [FastaFile]s balanced by total sequence length. Records are
+    /// assigned greedily (longest first) to the chunk with the smallest total length, the records
+    /// within each chunk are kept in the order they were read.
+    ///
+    /// If `n` is greater than the number of records, the trailing chunks are empty.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([
+    ///     ("a".to_string(), (None, "ACGTACGT".to_string())),
+    ///     ("b".to_string(), (None, "ACGT".to_string())),
+    ///     ("c".to_string(), (None, "ACGT".to_string())),
+    /// ]);
+    /// let chunks = fasta.split_into_chunks(2);
+    /// assert_eq!(chunks[0].ids().collect::<Vec<_>>(), vec!["a"]);
+    /// assert_eq!(chunks[1].ids().collect::<Vec<_>>(), vec!["b", "c"]);
+    /// ```
+    pub fn split_into_chunks(&self, n: usize) -> Vec<FastaFile> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut order: Vec<usize> = (0..self.0.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.0[i].1.len()));
+
+        let mut totals = vec![0usize; n];
+        let mut assignment = vec![0usize; self.0.len()];
+        for i in order {
+            let chunk = (0..n).min_by_key(|&chunk| totals[chunk]).unwrap_or(0);
+            totals[chunk] += self.0[i].1.len();
+            assignment[i] = chunk;
+        }
+
+        let mut out: Vec<FastaFile> = (0..n).map(|_| FastaFile::new()).collect();
+        for ((id, record), chunk) in self.0.iter().zip(assignment) {
+            out[chunk].0.insert(id.clone(), record.clone());
+        }
+        out
+    }
+
+    /// Return the normalised canonical tetranucleotide frequency vector (136 dimensions, ordered as
+    /// per [crate::sequence::kmer::canonical_kmers]) for each contig.
+    ///
+    /// The vectors are keyed by `(contig, index)`. If `window` is specified, each contig is split
+    /// into non-overlapping windows of that size (the final window may be shorter) and a vector is
+    /// returned for each, where `index` is the 0-based window number. Otherwise, the `index` of
+    /// each contig is 0.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("a".to_string(), (None, "ACGTACGTAC".to_string()))]);
+    /// assert_eq!(fasta.tnf_vectors(None)[&("a".to_string(), 0)].len(), 136);
+    /// assert_eq!(fasta.tnf_vectors(Some(5)).len(), 2);
+    /// ```
+    pub fn tnf_vectors(&self, window: Option<usize>) -> HashMap<(String, usize), Vec<f64>> {
+        let index = KmerIndex::new(4).unwrap();
+        let mut out = HashMap::new();
+        for (id, (_desc, seq)) in &self.0 {
+            match window {
+                Some(size) if size > 0 => {
+                    for (i, chunk) in seq.as_bytes().chunks(size).enumerate() {
+                        out.insert((id.clone(), i), index.frequency_vector(chunk));
+                    }
+                }
+                _ => {
+                    out.insert((id.clone(), 0), index.frequency_vector(seq.as_bytes()));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the [BaseCounts] of a sequence, or [None] if it does not exist.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("c1".to_string(), (None, "ACGTNN--".to_string()))]);
+    /// let counts = fasta.base_composition("c1").unwrap();
+    /// assert_eq!((counts.a, counts.n, counts.gap), (1, 2, 2));
+    /// ```
+    pub fn base_composition(&self, contig: &str) -> Option<BaseCounts> {
+        self.get_sequence(contig).map(BaseCounts::from_seq)
+    }
+
+    /// Returns the [BaseCounts] summed over all sequences.
+    pub fn base_composition_total(&self) -> BaseCounts {
+        let mut out = BaseCounts::default();
+        for (_, seq) in self.0.values() {
+            out += BaseCounts::from_seq(seq);
+        }
+        out
+    }
+
+    /// Returns the GC content of the assembly, i.e. the total `G + C` divided by the total
+    /// `A + C + G + T` across all sequences (ambiguous bases and gaps are excluded).
+    ///
+    /// This is weighted by length, and will differ from the mean of the per-sequence GC
+    /// content unless all sequences are the same length. An assembly without any `A`, `C`,
+    /// `G`, or `T` bases has a GC content of 0.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("c1".to_string(), (None, "GCAANN".to_string()))]);
+    /// assert_eq!(fasta.gc_content_total(), 0.5);
+    /// ```
+    pub fn gc_content_total(&self) -> f64 {
+        let counts = self.base_composition_total();
+        let acgt = counts.a + counts.c + counts.g + counts.t;
+        if acgt == 0 {
+            return 0.0;
+        }
+        (counts.g + counts.c) as f64 / acgt as f64
+    }
+
+    /// Returns the 1-based inclusive `(start, end)` coordinates of each run of consecutive `N`
+    /// bases (case-insensitive) on `contig` that is at least `min_len` long, or [None] if the
+    /// contig does not exist.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let fasta = FastaFile::from_iter([("c1".to_string(), (None, "ACNNNGTnA".to_string()))]);
+    /// assert_eq!(fasta.n_gaps("c1", 1), Some(vec![(3, 5), (8, 8)]));
+    /// assert_eq!(fasta.n_gaps("c1", 2), Some(vec![(3, 5)]));
+    /// ```
+    pub fn n_gaps(&self, contig: &str, min_len: usize) -> Option<Vec<(usize, usize)>> {
+        let seq = self.get_sequence(contig)?;
+        let mut out = vec![];
+        let mut run_start: Option<usize> = None;
+        for (idx, base) in seq.bytes().chain(std::iter::once(b'\0')).enumerate() {
+            match (base == b'N' || base == b'n', run_start) {
+                (true, None) => run_start = Some(idx),
+                (false, Some(start)) => {
+                    if idx - start >= min_len.max(1) {
+                        out.push((start + 1, idx));
+                    }
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        Some(out)
+    }
+
+    /// Return the fraction of bases in the assembly that are covered by a `CDS` feature in `gff`.
+    /// Overlapping features (on either strand) are only counted once, and features on contigs
+    /// that are not in this [FastaFile] are ignored. Returns 0 for an empty assembly.
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::genome::fasta_file::FastaFile;
+    /// use prole::genome::prodigal_gff::ProdigalGff;
+    ///
+    /// let fasta = FastaFile::from_iter([("c1".to_string(), (None, "A".repeat(100)))]);
+    /// let gff = ProdigalGff::from_bufreader(BufReader::new("c1\tProdigal\tCDS\t1\t60\t1.0\t+\t0\tID=1_1\n".as_bytes())).unwrap();
+    /// assert_eq!(fasta.coding_density(&gff), 0.6);
+    /// ```
+    pub fn coding_density(&self, gff: &ProdigalGff) -> f64 {
+        let total: usize = self.0.values().map(|(_desc, seq)| seq.len()).sum();
+        if total == 0 {
+            return 0.0;
+        }
+
+        let mut intervals: HashMap<&str, Vec<(u64, u64)>> = HashMap::new();
+        for feature in gff.cds() {
+            if let Some(len) = self.get_length(&feature.seq_id) {
+                let end = feature.end.min(len as u64);
+                if feature.start <= end {
+                    intervals.entry(feature.seq_id.as_str()).or_default().push((feature.start, end));
+                }
+            }
+        }
+
+        let mut coding: u64 = 0;
+        for contig_intervals in intervals.values_mut() {
+            contig_intervals.sort_unstable();
+            let (mut cur_start, mut cur_end) = contig_intervals[0];
+            for &(start, end) in &contig_intervals[1..] {
+                if start > cur_end {
+                    coding += cur_end - cur_start + 1;
+                    cur_start = start;
+                }
+                cur_end = cur_end.max(end);
+            }
+            coding += cur_end - cur_start + 1;
+        }
+        coding as f64 / total as f64
+    }
+
+    /// Translate each nucleotide sequence into a protein sequence, descriptions are unchanged.
+    ///
+    /// An error is returned if a sequence length is not a multiple of 3, or contains a codon
+    /// that is not in the translation table.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    /// use prole::sequence::tln_table::TranslationTable;
+    ///
+    /// let fasta = FastaFile::from_iter([("gene".to_string(), (None, "ATGaaaTAA".to_string()))]);
+    /// assert_eq!(fasta.translate(TranslationTable::T11).unwrap().get_sequence("gene"), Some("MK-"));
+    /// ```
+    pub fn translate(&self, table: TranslationTable) -> ProleResult<FastaFile> {
+        let out = self.0.iter()
+            .map(|(id, (desc, seq))| Ok((id.clone(), (desc.clone(), translate_record(id, seq, table)?))))
+            .collect::<ProleResult<IndexMap<_, _>>>()?;
+        Ok(FastaFile::from_map(out))
+    }
+
+    /// Translate each nucleotide sequence in the frame and strand with the fewest internal
+    /// stop codons (see [best_frame]). This is useful for gene files where the strand is unknown.
+    ///
+    /// The chosen frame (0, 1, or 2) and strand (`+` or `-`) are appended to the description,
+    /// e.g. `frame=2 strand=-`. Codons containing an ambiguous base (e.g. `N`) are translated
+    /// as `X`.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    /// use prole::sequence::tln_table::TranslationTable;
+    ///
+    /// let fasta = FastaFile::from_iter([("gene".to_string(), (Some("desc".to_string()), "TGATTAATCAGTCA".to_string()))]);
+    /// let prot = fasta.translate_best_frame(TranslationTable::T11).unwrap();
+    /// assert_eq!(prot.get_sequence("gene"), Some("INQS"));
+    /// assert_eq!(prot.get_description("gene"), Some("desc frame=2 strand=+"));
+    /// ```
+    pub fn translate_best_frame(&self, table: TranslationTable) -> ProleResult<FastaFile> {
+        let mut out = IndexMap::with_capacity(self.0.len());
+        for (id, (desc, seq)) in &self.0 {
+            let (frame, forward, prot) = best_frame(seq, table);
+            let tag = format!("frame={} strand={}", frame, if forward { '+' } else { '-' });
+            let desc = match desc {
+                Some(desc) => format!("{} {}", desc, tag),
+                None => tag,
+            };
+            out.insert(id.clone(), (Some(desc), prot));
+        }
+        Ok(FastaFile::from_map(out))
+    }
+
+    /// Equivalent to [FastaFile::translate], but the records are translated in parallel.
+    /// The order of the records is preserved.
+    #[cfg(feature = "rayon")]
+    pub fn translate_parallel(&self, table: TranslationTable) -> ProleResult<FastaFile> {
+        let records: Vec<(&String, &(Option<String>, String))> = self.0.iter().collect();
+        let out = records.into_par_iter()
+            .map(|(id, (desc, seq))| Ok((id.clone(), (desc.clone(), translate_record(id, seq, table)?))))
+            .collect::<ProleResult<Vec<_>>>()?;
+        Ok(FastaFile::from_map(out.into_iter().collect()))
+    }
+
+    /// Return the sequence of a gene located on `contig` between `start` and `end` (1-based,
+    /// inclusive). The sequence is reverse complemented if `strand` is negative.
+    ///
+    /// ```
+    /// use prole::genome::fasta_file::FastaFile;
+    ///
+    /// let mut fasta = FastaFile::new();
+    /// fasta.insert("contig".to_string(), None, "AATTGGCC".to_string());
+    /// assert_eq!(fasta.gene_sequence("contig", 2, 4, 1).unwrap(), "ATT");
+    /// assert_eq!(fasta.gene_sequence("contig", 2, 4, -1).unwrap(), "AAT");
+    /// ```
+    pub fn gene_sequence(&self, contig: &str, start: usize, end: usize, strand: i8) -> ProleResult<String> {
+        let seq = self.get_sequence(contig)
+            .ok_or_else(|| ProleError::Exit(format!("Missing sequence for: {}", contig)))?;
+        if start == 0 || start > end || end > seq.len() {
+            return Err(ProleError::Exit(format!("Invalid coordinates for {} (length {}): {}..{}", contig, seq.len(), start, end)));
+        }
+        let slice = &seq.as_bytes()[start - 1..end];
+        if strand < 0 {
+            String::from_utf8(revcomp(slice)).map_err(ProleError::Utf8Error)
+        } else {
+            String::from_utf8(slice.to_vec()).map_err(ProleError::Utf8Error)
+        }
+    }
+}
+
+
+/// Translate the nucleotide sequence of record `id` (case-insensitive).
+fn translate_record(id: &str, seq: &str, table: TranslationTable) -> ProleResult<String> {
+    if seq.len() % 3 != 0 {
+        return Err(ProleError::Exit(format!("Sequence length is not a multiple of 3 for: {}", id)));
+    }
+    seq.to_ascii_uppercase().as_bytes().chunks(3)
+        .map(|codon| {
+            std::str::from_utf8(codon).ok()
+                .and_then(|codon| table.try_translate(codon))
+                .ok_or_else(|| ProleError::Exit(format!("Invalid codon in {}: {}", id, String::from_utf8_lossy(codon))))
+        })
+        .collect()
+}
+
+/// Write a single FASTA record to `out`, the sequence is written on a single line.
+fn write_record<W: Write>(out: &mut W, id: &str, desc: Option<&str>, seq: &[u8]) -> ProleResult<()> {
+    write_record_wrapped(out, id, desc, seq, 0)
+}
+
+/// Write a single FASTA record to `out`, wrapping the sequence every `width` characters.
+/// The sequence is written on a single line if `width` is 0, an empty sequence is written as
+/// an empty line.
+fn write_record_wrapped<W: Write>(out: &mut W, id: &str, desc: Option<&str>, seq: &[u8], width: usize) -> ProleResult<()> {
+    match desc {
+        Some(desc) => writeln!(out, ">{} {}", id, desc),
+        None => writeln!(out, ">{}", id),
+    }.map_err(ProleError::IoError)?;
+    if seq.is_empty() {
+        return out.write_all(b"\n").map_err(ProleError::IoError);
+    }
+    let width = if width == 0 { seq.len() } else { width };
+    for line in seq.chunks(width) {
+        out.write_all(line).map_err(ProleError::IoError)?;
+        out.write_all(b"\n").map_err(ProleError::IoError)?;
+    }
+    Ok(())
+}
+
+/// Stream the FASTA file at `input` to `output`, only keeping records whose ID is in `keep`.
+/// The input is decompressed automatically (see [open_reader]), and the output is gz compressed
+/// if the extension is `.gz`.
+///
+/// Returns the number of records written.
+///
+/// ```no_run
+/// use std::collections::HashSet;
+/// use std::path::Path;
+/// use prole::genome::fasta_file::filter_to_path;
+///
+/// let keep: HashSet<String> = ["contig_1".to_string()].into_iter().collect();
+/// let n = filter_to_path(Path::new("/path/to/in.fna.gz"), Path::new("/path/to/out.fna"), &keep).unwrap();
+/// ```
+pub fn filter_to_path(input: &Path, output: &Path, keep: &HashSet<String>) -> ProleResult<usize> {
+    let reader = fasta::Reader::from_bufread(open_reader(input)?);
+    let mut writer = FileWriter::create(output)?;
+    let mut n_written = 0;
+    for record in reader.records() {
+        let record = record.map_err(ProleError::IoError)?;
+        if keep.contains(record.id()) {
+            write_record(&mut writer, record.id(), record.desc(), record.seq())?;
+            n_written += 1;
+        }
+    }
+    writer.finish()?;
+    Ok(n_written)
+}
+
+/// Stream the FASTA file at `input` to `output`, re-wrapping each sequence every `width`
+/// characters (or onto a single line if `width` is 0). Record IDs and descriptions are kept
+/// as-is. The input is decompressed automatically (see [open_reader]), and the output is gz
+/// compressed if the extension is `.gz`.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::genome::fasta_file::rewrap;
+///
+/// rewrap(Path::new("/path/to/in.fna.gz"), Path::new("/path/to/out.fna"), 80).unwrap();
+/// ```
+pub fn rewrap(input: &Path, output: &Path, width: usize) -> ProleResult<()> {
+    let reader = fasta::Reader::from_bufread(open_reader(input)?);
+    let mut writer = FileWriter::create(output)?;
+    for record in reader.records() {
+        let record = record.map_err(ProleError::IoError)?;
+        write_record_wrapped(&mut writer, record.id(), record.desc(), record.seq(), width)?;
+    }
+    writer.finish()
 }
 
 
@@ -107,6 +898,8 @@ mod tests {
     use flate2::write::GzEncoder;
     use tempfile::NamedTempFile;
 
+    use crate::util::parse::read_lines;
+
     use super::*;
 
     #[test]
@@ -120,13 +913,27 @@ mod tests {
         let result = FastaFile::from_path(&file.into_temp_path());
         assert!(result.is_ok());
         let result = result.unwrap();
-        assert_eq!(result.0.len(), 2);
+        assert_eq!(result.len(), 2);
         assert_eq!(result.get_sequence("foo").unwrap(), "ATGATG");
         assert_eq!(result.get_description("foo").unwrap(), "desc1");
         assert_eq!(result.get_sequence("bar").unwrap(), "CCGGTTAA");
         assert_eq!(result.get_description("bar").unwrap(), "desc2");
     }
 
+    #[test]
+    fn test_from_bufreader_with() {
+        let content = ">prot\nMSTK*\n>nucl\nATGAAA\n";
+
+        let result = FastaFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+        assert_eq!(result.get_sequence("prot").unwrap(), "MSTK");
+        assert_eq!(result.get_sequence("nucl").unwrap(), "ATGAAA");
+
+        let options = FastaReadOptions { strip_trailing_stop: false };
+        let result = FastaFile::from_bufreader_with(BufReader::new(content.as_bytes()), options).unwrap();
+        assert_eq!(result.get_sequence("prot").unwrap(), "MSTK*");
+        assert_eq!(result.get_sequence("nucl").unwrap(), "ATGAAA");
+    }
+
     #[test]
     fn test_from_path_gz_valid_file() {
         let mut file = NamedTempFile::new().unwrap();
@@ -139,10 +946,445 @@ mod tests {
         let result = FastaFile::from_path_gz(&file.into_temp_path());
         assert!(result.is_ok());
         let result = result.unwrap();
-        assert_eq!(result.0.len(), 2);
+        assert_eq!(result.len(), 2);
         assert_eq!(result.get_sequence("foo").unwrap(), "ATGATG");
         assert_eq!(result.get_description("foo").unwrap(), "desc1");
         assert_eq!(result.get_sequence("bar").unwrap(), "CCGGTTAA");
         assert_eq!(result.get_description("bar").unwrap(), "desc2");
     }
+
+    #[test]
+    fn test_longest_shortest() {
+        let empty = FastaFile::new();
+        assert_eq!(empty.longest(), None);
+        assert_eq!(empty.shortest(), None);
+
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ATG".to_string()));
+        map.insert("bar".to_string(), (None, "ATGATG".to_string()));
+        map.insert("baz".to_string(), (None, "CCCGGG".to_string()));
+        map.insert("qux".to_string(), (None, "".to_string()));
+        map.insert("quux".to_string(), (None, "".to_string()));
+        let fasta = FastaFile::from_iter(map);
+        assert_eq!(fasta.longest(), Some(("bar", 6)));
+        assert_eq!(fasta.shortest(), Some(("qux", 0)));
+    }
+
+    #[test]
+    fn test_tnf_vectors() {
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ATGAAACCCGGGTTTAAACG".to_string()));
+        map.insert("bar".to_string(), (None, "ACG".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        let key = |id: &str, i: usize| (id.to_string(), i);
+        let tnf = fasta.tnf_vectors(None);
+        assert_eq!(tnf.len(), 2);
+        assert_eq!(tnf[&key("foo", 0)].len(), 136);
+        assert!((tnf[&key("foo", 0)].iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(tnf[&key("bar", 0)].iter().sum::<f64>(), 0.0);
+
+        let tnf = fasta.tnf_vectors(Some(8));
+        let mut keys: Vec<&(String, usize)> = tnf.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![&key("bar", 0), &key("foo", 0), &key("foo", 1), &key("foo", 2)]);
+        for i in 0..3 {
+            assert_eq!(tnf[&key("foo", i)].len(), 136);
+            assert!((tnf[&key("foo", i)].iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+
+        // A window of a contig cannot collide with a contig named like a window
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ACGTACGT".to_string()));
+        map.insert("foo_0".to_string(), (None, "AAAAAAAA".to_string()));
+        assert_eq!(FastaFile::from_iter(map).tnf_vectors(Some(8)).len(), 2);
+    }
+
+    #[test]
+    fn test_iter_by_length() {
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ATG".to_string()));
+        map.insert("qux".to_string(), (None, "ATGATG".to_string()));
+        map.insert("bar".to_string(), (None, "CCCGGG".to_string()));
+        map.insert("baz".to_string(), (None, "A".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        let desc: Vec<(&str, &str)> = fasta.iter_by_length_desc().collect();
+        assert_eq!(desc, vec![("bar", "CCCGGG"), ("qux", "ATGATG"), ("foo", "ATG"), ("baz", "A")]);
+        let asc: Vec<&str> = fasta.iter_by_length_asc().map(|(id, _seq)| id).collect();
+        assert_eq!(asc, vec!["baz", "foo", "bar", "qux"]);
+        assert_eq!(FastaFile::new().iter_by_length_desc().count(), 0);
+    }
+
+    #[test]
+    fn test_validate() {
+        let fasta = FastaFile::from_reader(">a\nACGT\n>b desc\n>c\nAC\n".as_bytes()).unwrap();
+        assert_eq!(fasta.get_sequence("b"), Some(""));
+        match fasta.validate() {
+            Err(ProleError::Exit(msg)) => assert!(msg.ends_with(": b")),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(FastaFile::from_reader(">a\nACGT\n".as_bytes()).unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_split_into_chunks() {
+        let fasta = FastaFile::from_iter([
+            ("a".to_string(), (None, "A".repeat(2))),
+            ("b".to_string(), (None, "A".repeat(10))),
+            ("c".to_string(), (Some("desc".to_string()), "A".repeat(5))),
+            ("d".to_string(), (None, "A".repeat(6))),
+            ("e".to_string(), (None, "A".repeat(3))),
+        ]);
+        let chunks = fasta.split_into_chunks(2);
+        let ids = |x: &FastaFile| x.ids().map(|id| id.to_string()).collect::<Vec<String>>();
+        let total = |x: &FastaFile| x.iter().map(|(_, _, seq)| seq.len()).sum::<usize>();
+        assert_eq!(ids(&chunks[0]), vec!["b", "e"]);
+        assert_eq!(ids(&chunks[1]), vec!["a", "c", "d"]);
+        assert_eq!((total(&chunks[0]), total(&chunks[1])), (13, 13));
+        assert_eq!(chunks[1].get_description("c"), Some("desc"));
+
+        let chunks = fasta.split_into_chunks(7);
+        assert_eq!(chunks.len(), 7);
+        assert!(chunks[..5].iter().all(|x| x.len() == 1));
+        assert!(chunks[5..].iter().all(|x| x.is_empty()));
+        assert!(fasta.split_into_chunks(0).is_empty());
+    }
+
+    #[test]
+    fn test_count_records() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["contigs.fna", "contigs.fna.gz"] {
+            let path = dir.path().join(name);
+            let mut writer = FileWriter::create(&path).unwrap();
+            writeln!(writer, ">foo desc>1").unwrap();
+            writeln!(writer, "ATG>ATG").unwrap();
+            writeln!(writer, " >not_a_header").unwrap();
+            writeln!(writer, ">bar").unwrap();
+            writeln!(writer, "CCGG").unwrap();
+            write!(writer, ">baz").unwrap();
+            writer.finish().unwrap();
+            assert_eq!(FastaFile::count_records(&path).unwrap(), 3);
+        }
+        assert!(FastaFile::count_records(&dir.path().join("missing.fna")).is_err());
+    }
+
+    #[test]
+    fn test_rewrap() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.fna");
+        std::fs::write(&input, ">foo desc 1\nACGTACGTAC\n>bar\nAC\nGT\n").unwrap();
+
+        let output = dir.path().join("out.fna.gz");
+        rewrap(&input, &output, 4).unwrap();
+        let lines: Vec<String> = read_lines(open_reader(&output).unwrap()).map(|x| x.unwrap()).collect();
+        assert_eq!(lines, vec![">foo desc 1", "ACGT", "ACGT", "AC", ">bar", "ACGT"]);
+        assert!(lines.iter().filter(|x| !x.starts_with('>')).all(|x| x.len() <= 4));
+
+        let unwrapped = dir.path().join("unwrapped.fna");
+        rewrap(&output, &unwrapped, 0).unwrap();
+        assert_eq!(std::fs::read_to_string(&unwrapped).unwrap(), ">foo desc 1\nACGTACGTAC\n>bar\nACGT\n");
+    }
+
+    #[test]
+    fn test_coding_density() {
+        let mut map = IndexMap::new();
+        map.insert("c1".to_string(), (None, "A".repeat(100)));
+        map.insert("c2".to_string(), (None, "A".repeat(100)));
+        let fasta = FastaFile::from_iter(map);
+
+        let content = [
+            "##gff-version  3",
+            "c1\tProdigal\tCDS\t1\t30\t1.0\t+\t0\tID=1_1",
+            "c1\tProdigal\tCDS\t21\t50\t1.0\t-\t0\tID=1_2",
+            "c1\tProdigal\tCDS\t25\t40\t1.0\t+\t0\tID=1_3",
+            "c1\tProdigal\tCDS\t51\t60\t1.0\t+\t0\tID=1_4",
+            "c1\tProdigal\ttRNA\t61\t100\t1.0\t+\t0\tID=1_5",
+            "c2\tProdigal\tCDS\t91\t120\t1.0\t+\t0\tID=2_1",
+            "c3\tProdigal\tCDS\t1\t100\t1.0\t+\t0\tID=3_1",
+        ].join("\n");
+        let gff = ProdigalGff::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+
+        // c1: 1..60 (60), c2: 91..100 (10)
+        assert_eq!(fasta.coding_density(&gff), 70.0 / 200.0);
+        assert_eq!(FastaFile::new().coding_density(&gff), 0.0);
+        assert_eq!(fasta.coding_density(&ProdigalGff(vec![])), 0.0);
+    }
+
+    #[test]
+    fn test_base_composition() {
+        let mut map = IndexMap::new();
+        map.insert("c1".to_string(), (None, "AACGTnnN-".to_string()));
+        map.insert("c2".to_string(), (None, "ggRY..".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        assert_eq!(fasta.base_composition("c1"), Some(BaseCounts { a: 2, c: 1, g: 1, t: 1, n: 3, other: 0, gap: 1 }));
+        assert_eq!(fasta.base_composition("c3"), None);
+        assert_eq!(fasta.base_composition_total(), BaseCounts { a: 2, c: 1, g: 3, t: 1, n: 3, other: 2, gap: 3 });
+    }
+
+    #[test]
+    fn test_gc_content_total() {
+        let mut map = IndexMap::new();
+        map.insert("long".to_string(), (None, "GC".repeat(5) + &"A".repeat(85)));
+        map.insert("short".to_string(), (None, "GGCCG".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        // Weighted by length (15 / 100), rather than the mean of the per-sequence values
+        assert!((fasta.gc_content_total() - 0.15).abs() < 1e-12);
+        assert_eq!(FastaFile::new().gc_content_total(), 0.0);
+    }
+
+    #[test]
+    fn test_n_gaps() {
+        let fasta = FastaFile::from_iter([("c1".to_string(), (None, "NNACGTNNNNNacgtnnA".to_string()))]);
+        assert_eq!(fasta.n_gaps("c1", 0), Some(vec![(1, 2), (7, 11), (16, 17)]));
+        assert_eq!(fasta.n_gaps("c1", 3), Some(vec![(7, 11)]));
+        assert_eq!(fasta.n_gaps("c1", 6), Some(vec![]));
+        assert_eq!(fasta.n_gaps("c2", 1), None);
+
+        let fasta = FastaFile::from_iter([("c1".to_string(), (None, "ANNN".to_string()))]);
+        assert_eq!(fasta.n_gaps("c1", 3), Some(vec![(2, 4)]));
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (Some("desc".to_string()), "ATGAAACCCTAA".to_string()));
+        map.insert("bar".to_string(), (None, "atgtgg".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        let prot = fasta.translate(TranslationTable::T11).unwrap();
+        assert_eq!(prot.ids().collect::<Vec<_>>(), vec!["foo", "bar"]);
+        assert_eq!(prot.get_sequence("foo"), Some("MKP-"));
+        assert_eq!(prot.get_description("foo"), Some("desc"));
+        assert_eq!(prot.get_sequence("bar"), Some("MW"));
+
+        let invalid = FastaFile::from_iter([("foo".to_string(), (None, "ATGA".to_string()))]);
+        assert!(invalid.translate(TranslationTable::T11).is_err());
+        let invalid = FastaFile::from_iter([("foo".to_string(), (None, "ATGNNN".to_string()))]);
+        assert!(invalid.translate(TranslationTable::T11).is_err());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_translate_parallel() {
+        let codons = ["ATG", "AAA", "CCC", "GGG", "TTT", "TAA", "TGG"];
+        let mut map = IndexMap::new();
+        for i in 0..500 {
+            let seq: String = (0..(i % 17) + 1).map(|j| codons[(i + j) % codons.len()]).collect();
+            map.insert(format!("gene_{}", i), (Some(format!("desc {}", i)), seq));
+        }
+        let fasta = FastaFile::from_iter(map);
+
+        let serial = fasta.translate(TranslationTable::T11).unwrap();
+        let parallel = fasta.translate_parallel(TranslationTable::T11).unwrap();
+        assert!(serial.iter().eq(parallel.iter()));
+
+        let invalid = FastaFile::from_iter([("foo".to_string(), (None, "ATGA".to_string()))]);
+        assert!(invalid.translate_parallel(TranslationTable::T11).is_err());
+    }
+
+    #[test]
+    fn test_translate_best_frame() {
+        let mut map = IndexMap::new();
+        map.insert("fwd".to_string(), (Some("desc".to_string()), "tgattaatcagtca".to_string()));
+        map.insert("rev".to_string(), (None, "TGATTAAGTAATTA".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        let prot = fasta.translate_best_frame(TranslationTable::T11).unwrap();
+        assert_eq!(prot.ids().collect::<Vec<_>>(), vec!["fwd", "rev"]);
+        assert_eq!(prot.get_sequence("fwd"), Some("INQS"));
+        assert_eq!(prot.get_description("fwd"), Some("desc frame=2 strand=+"));
+        assert_eq!(prot.get_sequence("rev"), Some("NYLI"));
+        assert_eq!(prot.get_description("rev"), Some("frame=1 strand=-"));
+
+        let ambiguous = FastaFile::from_iter([("foo".to_string(), (None, "ATGNNNAAAT".to_string()))]);
+        let prot = ambiguous.translate_best_frame(TranslationTable::T11).unwrap();
+        assert_eq!(prot.get_sequence("foo"), Some("MXK"));
+    }
+
+    #[test]
+    fn test_gene_sequence() {
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ATGAAACCCGGG".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        assert_eq!(fasta.gene_sequence("foo", 1, 6, 1).unwrap(), "ATGAAA");
+        assert_eq!(fasta.gene_sequence("foo", 7, 12, 1).unwrap(), "CCCGGG");
+        assert_eq!(fasta.gene_sequence("foo", 1, 6, -1).unwrap(), "TTTCAT");
+        assert_eq!(fasta.gene_sequence("foo", 1, 12, -1).unwrap(), "CCCGGGTTTCAT");
+        assert!(fasta.gene_sequence("foo", 0, 6, 1).is_err());
+        assert!(fasta.gene_sequence("foo", 6, 13, 1).is_err());
+        assert!(fasta.gene_sequence("bar", 1, 6, 1).is_err());
+    }
+
+    #[test]
+    fn test_get_sequence_ci() {
+        let mut map = IndexMap::new();
+        map.insert("Contig_A".to_string(), (None, "ATG".to_string()));
+        map.insert("contig_a".to_string(), (None, "CCC".to_string()));
+        map.insert("CONTIG_B".to_string(), (None, "GGG".to_string()));
+        let mut fasta = FastaFile::from_iter(map);
+
+        assert_eq!(fasta.get_sequence_ci("contig_a"), Some("CCC"));
+        assert_eq!(fasta.get_sequence_ci("CONTIG_A"), Some("ATG"));
+        assert_eq!(fasta.get_sequence_ci("contig_b"), Some("GGG"));
+        assert_eq!(fasta.get_sequence_ci("contig_c"), None);
+
+        let index = fasta.lowercase_index();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index["contig_a"], "Contig_A");
+        assert_eq!(index["contig_b"], "CONTIG_B");
+
+        // The cached index is rebuilt once the records change
+        fasta.insert("Contig_C".to_string(), None, "TTT".to_string());
+        assert_eq!(fasta.get_sequence_ci("contig_c"), Some("TTT"));
+        assert_eq!(fasta.deduplicate_by_sequence(), vec![]);
+        fasta.merge(FastaFile::from_iter([("contig_d".to_string(), (None, "AAA".to_string()))]), ConflictPolicy::Error).unwrap();
+        assert_eq!(fasta.get_sequence_ci("CONTIG_D"), Some("AAA"));
+        fasta.insert("Contig_E".to_string(), None, "TTT".to_string());
+        fasta.deduplicate_by_sequence();
+        assert_eq!(fasta.get_sequence_ci("contig_e"), None);
+        assert_eq!(fasta.get_sequence_ci("contig_d"), Some("AAA"));
+
+        // Records changed through .0 fall back to a linear scan
+        fasta.0.insert("Contig_F".to_string(), (None, "CCC".to_string()));
+        assert_eq!(fasta.get_sequence_ci("contig_f"), Some("CCC"));
+        fasta.0.swap_remove("Contig_A");
+        assert_eq!(fasta.get_sequence_ci("CONTIG_A"), Some("CCC"));
+        fasta.0.swap_remove("Contig_F");
+        assert_eq!(fasta.get_sequence_ci("contig_f"), None);
+    }
+
+    #[test]
+    fn test_get_sequences() {
+        let mut map = IndexMap::new();
+        map.insert("foo".to_string(), (None, "ATG".to_string()));
+        map.insert("bar".to_string(), (None, "CCC".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        assert_eq!(fasta.get_sequences(&["bar", "baz", "foo"]), vec![Some("CCC"), None, Some("ATG")]);
+        assert!(fasta.get_sequences(&[]).is_empty());
+        assert!(fasta.contains("foo"));
+        assert!(!fasta.contains("baz"));
+    }
+
+    #[test]
+    fn test_filter_to_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.fna.gz");
+        let mut writer = FileWriter::create(&input).unwrap();
+        writer.write_all(b">foo desc1\nATGATG\nATG\n>bar desc2\nCCGGTTAA\n>baz\nTTTT\n").unwrap();
+        writer.finish().unwrap();
+
+        let output = dir.path().join("out.fna");
+        let keep: HashSet<String> = ["bar".to_string(), "missing".to_string()].into_iter().collect();
+        let n = filter_to_path(&input, &output, &keep).unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), ">bar desc2\nCCGGTTAA\n");
+
+        let output_gz = dir.path().join("out.fna.gz");
+        let keep: HashSet<String> = ["foo".to_string(), "baz".to_string()].into_iter().collect();
+        let n = filter_to_path(&input, &output_gz, &keep).unwrap();
+        assert_eq!(n, 2);
+        let result = FastaFile::from_path_gz(&output_gz).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.get_sequence("foo").unwrap(), "ATGATGATG");
+        assert_eq!(result.get_description("baz"), None);
+
+        // An empty sequence is written as an empty line
+        std::fs::write(&input, ">foo\n>bar\nACGT\n").unwrap();
+        let keep: HashSet<String> = ["foo".to_string(), "bar".to_string()].into_iter().collect();
+        assert_eq!(filter_to_path(&input, &output, &keep).unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), ">foo\n\n>bar\nACGT\n");
+    }
+
+    #[test]
+    fn test_merge() {
+        let new = || {
+            let mut map = IndexMap::new();
+            map.insert("a".to_string(), (Some("self".to_string()), "AAAA".to_string()));
+            map.insert("b".to_string(), (Some("self".to_string()), "CCCC".to_string()));
+            FastaFile::from_iter(map)
+        };
+        let other = || {
+            let mut map = IndexMap::new();
+            map.insert("c".to_string(), (Some("other".to_string()), "GGGG".to_string()));
+            map.insert("b".to_string(), (Some("other".to_string()), "TTTT".to_string()));
+            map.insert("a".to_string(), (Some("other".to_string()), "TTTT".to_string()));
+            FastaFile::from_iter(map)
+        };
+
+        // Both b and a collide, b is reported as it comes first in other
+        let mut fasta = new();
+        match fasta.merge(other(), ConflictPolicy::Error) {
+            Err(ProleError::Exit(msg)) => assert_eq!(msg, "Duplicate ID found when merging: b"),
+            _ => panic!("Expected a conflict error"),
+        }
+        assert_eq!(fasta.len(), 2);
+        assert_eq!(fasta.get_sequence("a").unwrap(), "AAAA");
+
+        let mut fasta = new();
+        fasta.merge(other(), ConflictPolicy::KeepSelf).unwrap();
+        assert_eq!(fasta.len(), 3);
+        assert_eq!(fasta.get_sequence("a").unwrap(), "AAAA");
+        assert_eq!(fasta.get_description("b").unwrap(), "self");
+        assert_eq!(fasta.get_sequence("c").unwrap(), "GGGG");
+
+        let mut fasta = new();
+        fasta.merge(other(), ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(fasta.len(), 3);
+        assert_eq!(fasta.get_sequence("a").unwrap(), "TTTT");
+        assert_eq!(fasta.get_description("b").unwrap(), "other");
+        assert_eq!(fasta.get_sequence("c").unwrap(), "GGGG");
+
+        let mut fasta = new();
+        let mut map = IndexMap::new();
+        map.insert("d".to_string(), (None, "GGGG".to_string()));
+        fasta.merge(FastaFile::from_iter(map), ConflictPolicy::Error).unwrap();
+        assert_eq!(fasta.len(), 3);
+    }
+
+    #[test]
+    fn test_deduplicate_by_sequence() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, ">z_first").unwrap();
+        writeln!(file, "ATGATG").unwrap();
+        writeln!(file, ">unique").unwrap();
+        writeln!(file, "CCGG").unwrap();
+        writeln!(file, ">a_second").unwrap();
+        writeln!(file, "atgATG").unwrap();
+        writeln!(file, ">m_third").unwrap();
+        writeln!(file, "ATGATG").unwrap();
+
+        let mut fasta = FastaFile::from_path(&file.into_temp_path()).unwrap();
+        let removed = fasta.deduplicate_by_sequence();
+        assert_eq!(removed, vec![
+            ("a_second".to_string(), "z_first".to_string()),
+            ("m_third".to_string(), "z_first".to_string()),
+        ]);
+        assert_eq!(fasta.ids().collect::<Vec<_>>(), vec!["z_first", "unique"]);
+        assert!(fasta.deduplicate_by_sequence().is_empty());
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut map = IndexMap::new();
+        map.insert("contig1".to_string(), (Some("desc1".to_string()), "AAAA".to_string()));
+        map.insert("contig2".to_string(), (None, "CCCC".to_string()));
+        let fasta = FastaFile::from_iter(map);
+
+        let result = fasta.rename(|id| format!("G000123_{}", id)).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.get_sequence("G000123_contig1").unwrap(), "AAAA");
+        assert_eq!(result.get_description("G000123_contig1").unwrap(), "desc1");
+        assert_eq!(result.get_sequence("G000123_contig2").unwrap(), "CCCC");
+        assert_eq!(result.get_description("G000123_contig2"), None);
+
+        match fasta.rename(|_| "same".to_string()) {
+            Err(ProleError::Exit(msg)) => assert_eq!(msg, "Duplicate ID found when renaming: same"),
+            _ => panic!("Expected a duplicate error"),
+        }
+    }
 }
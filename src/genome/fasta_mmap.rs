@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use crate::error::{ProleError, ProleResult};
+use crate::util::io::is_gz;
+use crate::util::parse::read_lines;
+
+/// A single record from a samtools FASTA index (`.fai`) file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaiRecord {
+    /// The number of bases in the sequence.
+    pub length: usize,
+    /// The byte offset of the first base of the sequence.
+    pub offset: usize,
+    /// The number of bases on each line.
+    pub line_bases: usize,
+    /// The number of bytes on each line (including the newline).
+    pub line_width: usize,
+}
+
+impl FaiRecord {
+    /// Parse a single (tab separated) line from a `.fai` file, returning the name and record.
+    pub fn from_string(string: &str) -> ProleResult<(String, Self)> {
+        let cols: Vec<&str> = string.trim_end().split('\t').collect();
+        if cols.len() < 5 {
+            return Err(ProleError::Exit(format!("Expected at least 5 columns in FASTA index: {}", string)));
+        }
+        let record = Self {
+            length: cols[1].parse().map_err(ProleError::ParseIntError)?,
+            offset: cols[2].parse().map_err(ProleError::ParseIntError)?,
+            line_bases: cols[3].parse().map_err(ProleError::ParseIntError)?,
+            line_width: cols[4].parse().map_err(ProleError::ParseIntError)?,
+        };
+        if record.line_bases == 0 && record.length > 0 {
+            return Err(ProleError::Exit(format!("Invalid line length in FASTA index: {}", string)));
+        }
+        Ok((cols[0].to_string(), record))
+    }
+}
+
+/// A memory-mapped FASTA file that serves sub-sequences using its `.fai` index.
+///
+/// Only the requested region is copied into memory, making this well suited to repeatedly
+/// querying small regions of a large genome. The FASTA file **must be uncompressed**, as
+/// the index refers to byte offsets in the file on disk (gzip/bgzip files are rejected).
+///
+/// This requires the `memmap2` feature.
+pub struct MmapFasta {
+    mmap: Mmap,
+    index: HashMap<String, FaiRecord>,
+}
+
+impl MmapFasta {
+    /// Open the FASTA file at [Path] using the index at `{path}.fai`.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::genome::fasta_mmap::MmapFasta;
+    ///
+    /// let fasta = MmapFasta::open(Path::new("/path/to/genome.fna")).unwrap();
+    /// let region = fasta.fetch("NC_000913.3", 100, 200).unwrap();
+    /// ```
+    pub fn open(path: &Path) -> ProleResult<Self> {
+        let mut fai_path = PathBuf::from(path).into_os_string();
+        fai_path.push(".fai");
+        Self::open_with_index(path, Path::new(&fai_path))
+    }
+
+    /// Open the FASTA file at [Path] using the index at `fai_path`.
+    pub fn open_with_index(path: &Path, fai_path: &Path) -> ProleResult<Self> {
+        if is_gz(path) {
+            return Err(ProleError::Exit(format!("Memory-mapped FASTA files must be uncompressed: {}", path.display())));
+        }
+
+        let reader = BufReader::new(File::open(fai_path).map_err(ProleError::IoError)?);
+        let mut index = HashMap::new();
+        for line in read_lines(reader) {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let (name, record) = FaiRecord::from_string(&line)?;
+            if index.insert(name.clone(), record).is_some() {
+                return Err(ProleError::Exit(format!("Duplicate sequence in FASTA index: {}", name)));
+            }
+        }
+
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        // Safety: the file must not be modified while it is mapped.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(ProleError::IoError)?;
+        Ok(Self { mmap, index })
+    }
+
+    /// Returns the length of `contig`, if it exists in the index.
+    pub fn contig_length(&self, contig: &str) -> Option<usize> {
+        self.index.get(contig).map(|record| record.length)
+    }
+
+    /// Returns the names of the contigs in the index (unordered).
+    pub fn contigs(&self) -> impl Iterator<Item=&str> {
+        self.index.keys().map(|name| name.as_str())
+    }
+
+    /// Fetch the sub-sequence of `contig` from `start` (inclusive) to `end` (exclusive),
+    /// using 0-based coordinates.
+    pub fn fetch(&self, contig: &str, start: usize, end: usize) -> ProleResult<String> {
+        let record = self.index.get(contig)
+            .ok_or_else(|| ProleError::Exit(format!("Sequence not found in FASTA index: {}", contig)))?;
+        if start > end || end > record.length {
+            return Err(ProleError::Exit(format!("Invalid region {}:{}-{} (length {})", contig, start, end, record.length)));
+        }
+
+        let mut out = Vec::with_capacity(end - start);
+        let mut pos = start;
+        while pos < end {
+            let col = pos % record.line_bases;
+            let n_bases = (record.line_bases - col).min(end - pos);
+            let byte = record.offset + (pos / record.line_bases) * record.line_width + col;
+            let bases = self.mmap.get(byte..byte + n_bases)
+                .ok_or_else(|| ProleError::Exit(format!("FASTA index is inconsistent with the file: {}", contig)))?;
+            out.extend_from_slice(bases);
+            pos += n_bases;
+        }
+        String::from_utf8(out).map_err(ProleError::Utf8Error)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_fasta(dir: &Path) -> PathBuf {
+        let path = dir.join("genome.fna");
+        let mut file = File::create(&path).unwrap();
+        write!(file, ">c1 desc\nACGTA\nCCGGT\nTT\n>c2\nGGGG\n").unwrap();
+        let mut fai = File::create(dir.join("genome.fna.fai")).unwrap();
+        writeln!(fai, "c1\t12\t9\t5\t6").unwrap();
+        writeln!(fai, "c2\t4\t28\t5\t6").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let fasta = MmapFasta::open(&write_fasta(dir.path())).unwrap();
+        assert_eq!(fasta.contig_length("c1"), Some(12));
+        assert_eq!(fasta.fetch("c1", 0, 12).unwrap(), "ACGTACCGGTTT");
+        assert_eq!(fasta.fetch("c1", 3, 8).unwrap(), "TACCG");
+        assert_eq!(fasta.fetch("c1", 5, 5).unwrap(), "");
+        assert_eq!(fasta.fetch("c2", 1, 4).unwrap(), "GGG");
+        assert!(fasta.fetch("c1", 0, 13).is_err());
+        assert!(fasta.fetch("c1", 4, 3).is_err());
+        assert!(fasta.fetch("c3", 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_open_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(MmapFasta::open(&dir.path().join("missing.fna")).is_err());
+        assert!(MmapFasta::open(Path::new("genome.fna.gz")).is_err());
+    }
+}
@@ -1,11 +1,53 @@
+use std::collections::HashMap;
+
 use lazy_static::lazy_static;
 use regex::Regex;
 
 lazy_static! {
-    static ref RE_CANONICAL: Regex = Regex::new(r"^(?:(?:(?:GB_)?(GCA_))|(?:(?:RS_)?(GCF_)))(\d{9})\.(\d)$").unwrap();
+    static ref RE_CANONICAL: Regex = Regex::new(r"^(?:(?:(?:GB_)?(GCA_))|(?:(?:RS_)?(GCF_)))(\d{9})\.(\d+)$").unwrap();
+    static ref RE_CANONICAL_FORM: Regex = Regex::new(r"^G\d{9}$").unwrap();
+    static ref RE_UBA: Regex = Regex::new(r"^(?:UBA|U_)\d+$").unwrap();
+}
+
+
+/// The kind of accession stored within a [GenomeId].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AccessionKind {
+    /// A GenBank accession, optionally with the GTDB database prefix (e.g. `GB_GCA_123456789.1`).
+    GenBank,
+    /// A RefSeq accession, optionally with the GTDB database prefix (e.g. `RS_GCF_123456789.1`).
+    RefSeq,
+    /// An accession in canonical form (e.g. `G123456789`).
+    Canonical,
+    /// A genome from the uncultivated bacteria and archaea (UBA) dataset (e.g. `UBA12345`, or `U_12345`).
+    Uba,
+    /// Any other accession.
+    Other,
+}
+
+
+/// The NCBI database of a GenBank/RefSeq assembly accession.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Database {
+    /// A GenBank (`GCA_`) accession.
+    GenBank,
+    /// A RefSeq (`GCF_`) accession.
+    RefSeq,
 }
 
 
+/// The components of a GenBank/RefSeq accession, see [GenomeId::parse].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ParsedAccession {
+    pub database: Database,
+    /// The 9-digit assembly number (e.g. `123456789` for `GCA_123456789.1`).
+    pub accession_number: u32,
+    /// The assembly version (e.g. `1` for `GCA_123456789.1`).
+    pub version: u8,
+    /// The accession in canonical form (e.g. `G123456789`), see [GenomeId::to_canonical].
+    pub canonical: GenomeId,
+}
+
 /// A genome accession, there is no restriction to the format of the accession.
 ///
 /// ```
@@ -15,11 +57,59 @@ lazy_static! {
 /// let _ = GenomeId("RS_GCF_123456789.1".to_string());
 /// let _ = GenomeId("G123456789".to_string());
 /// ```
-#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenomeId(pub String);
 
 
 impl GenomeId {
+    /// Returns the [AccessionKind] of this accession.
+    ///
+    /// ```
+    /// use prole::genome::genome_id::{AccessionKind, GenomeId};
+    ///
+    /// assert_eq!(GenomeId("RS_GCF_123456789.1".to_string()).kind(), AccessionKind::RefSeq);
+    /// assert_eq!(GenomeId("UBA12345".to_string()).kind(), AccessionKind::Uba);
+    /// ```
+    pub fn kind(&self) -> AccessionKind {
+        if let Some(hit) = RE_CANONICAL.captures(&self.0) {
+            if hit.get(1).is_some() {
+                AccessionKind::GenBank
+            } else {
+                AccessionKind::RefSeq
+            }
+        } else if RE_CANONICAL_FORM.is_match(&self.0) {
+            AccessionKind::Canonical
+        } else if RE_UBA.is_match(&self.0) {
+            AccessionKind::Uba
+        } else {
+            AccessionKind::Other
+        }
+    }
+
+    /// Parses the components of a GenBank/RefSeq accession (optionally with the GTDB database
+    /// prefix) in a single pass, otherwise None is returned. None is also returned if the
+    /// version does not fit in a [u8].
+    ///
+    /// ```
+    /// use prole::genome::genome_id::{Database, GenomeId};
+    ///
+    /// let parsed = GenomeId("RS_GCF_123456789.2".to_string()).parse().unwrap();
+    /// assert_eq!(parsed.database, Database::RefSeq);
+    /// assert_eq!((parsed.accession_number, parsed.version), (123456789, 2));
+    /// assert_eq!(parsed.canonical, GenomeId("G123456789".to_string()));
+    /// ```
+    pub fn parse(&self) -> Option<ParsedAccession> {
+        let hit = RE_CANONICAL.captures(&self.0)?;
+        let database = if hit.get(1).is_some() { Database::GenBank } else { Database::RefSeq };
+        Some(ParsedAccession {
+            database,
+            accession_number: hit[3].parse().ok()?,
+            version: hit[4].parse().ok()?,
+            canonical: GenomeId(format!("G{}", &hit[3])),
+        })
+    }
+
     /// Converts the accession to canonical form, if it is a valid GenBank/RefSeq identifier,
     /// otherwise None is returned.
     ///
@@ -59,12 +149,49 @@ impl GenomeId {
         }
         None
     }
+
+    /// Splits the GTDB database prefix (`GB_` or `RS_`) from the accession, returning the prefix
+    /// (if present) and the remaining accession. Unlike [GenomeId::to_short], the accession is
+    /// not otherwise validated.
+    /// ```
+    /// use prole::genome::genome_id::GenomeId;
+    ///
+    /// let gid = GenomeId("RS_GCF_123456789.1".to_string());
+    /// assert_eq!(gid.split_db_prefix(), (Some("RS_".to_string()), GenomeId("GCF_123456789.1".to_string())));
+    /// ```
+    #[must_use]
+    pub fn split_db_prefix(&self) -> (Option<String>, Self) {
+        for prefix in ["GB_", "RS_"] {
+            if let Some(accession) = self.0.strip_prefix(prefix) {
+                return (Some(prefix.to_string()), GenomeId(accession.to_string()));
+            }
+        }
+        (None, self.clone())
+    }
+}
+
+
+/// Map each [GenomeId] to its canonical form (see [GenomeId::to_canonical]), or [None] if it
+/// could not be converted. Duplicate inputs are only present once.
+///
+/// ```
+/// use prole::genome::genome_id::{canonicalize_all, GenomeId};
+///
+/// let ids = vec![GenomeId("RS_GCF_123456789.1".to_string()), GenomeId("UBA12345".to_string())];
+/// let canonical = canonicalize_all(&ids);
+/// assert_eq!(canonical[&ids[0]], Some(GenomeId("G123456789".to_string())));
+/// assert_eq!(canonical[&ids[1]], None);
+/// ```
+pub fn canonicalize_all<'a, I: IntoIterator<Item=&'a GenomeId>>(ids: I) -> HashMap<GenomeId, Option<GenomeId>> {
+    ids.into_iter()
+        .map(|genome_id| (genome_id.clone(), genome_id.to_canonical()))
+        .collect()
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::genome::genome_id::GenomeId;
+    use crate::genome::genome_id::{AccessionKind, canonicalize_all, Database, GenomeId};
 
     #[test]
     fn test_to_canonical() {
@@ -72,6 +199,10 @@ mod tests {
         assert_eq!(GenomeId("RS_GCF_123456789.1".to_string()).to_canonical(), Some(GenomeId("G123456789".to_string())));
         assert_eq!(GenomeId("GCA_123456789.1".to_string()).to_canonical(), Some(GenomeId("G123456789".to_string())));
         assert_eq!(GenomeId("GCF_123456789.1".to_string()).to_canonical(), Some(GenomeId("G123456789".to_string())));
+        assert_eq!(GenomeId("GCF_123456789.10".to_string()).to_canonical(), Some(GenomeId("G123456789".to_string())));
+        assert_eq!(GenomeId("UBA12345".to_string()).to_canonical(), None);
+        assert_eq!(GenomeId("G123456789".to_string()).to_canonical(), None);
+        assert_eq!(GenomeId("GB_GCF_123456789.1".to_string()).to_canonical(), None);
         assert_eq!(GenomeId("something".to_string()).to_canonical(), None);
         assert_eq!(GenomeId("".to_string()).to_canonical(), None);
     }
@@ -82,7 +213,72 @@ mod tests {
         assert_eq!(GenomeId("RS_GCF_123456789.1".to_string()).to_short(), Some(GenomeId("GCF_123456789.1".to_string())));
         assert_eq!(GenomeId("GCA_123456789.1".to_string()).to_short(), Some(GenomeId("GCA_123456789.1".to_string())));
         assert_eq!(GenomeId("GCF_123456789.1".to_string()).to_short(), Some(GenomeId("GCF_123456789.1".to_string())));
+        assert_eq!(GenomeId("RS_GCF_123456789.10".to_string()).to_short(), Some(GenomeId("GCF_123456789.10".to_string())));
         assert_eq!(GenomeId("something".to_string()).to_short(), None);
         assert_eq!(GenomeId("".to_string()).to_short(), None);
     }
+
+    #[test]
+    fn test_parse() {
+        let parsed = GenomeId("GB_GCA_012345678.1".to_string()).parse().unwrap();
+        assert_eq!(parsed.database, Database::GenBank);
+        assert_eq!(parsed.accession_number, 12345678);
+        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.canonical, GenomeId("G012345678".to_string()));
+
+        let parsed = GenomeId("GCF_123456789.10".to_string()).parse().unwrap();
+        assert_eq!((parsed.database, parsed.accession_number, parsed.version), (Database::RefSeq, 123456789, 10));
+
+        for genome_id in ["GCA_123456789.1", "RS_GCF_123456789.3", "GB_GCA_000000001.2"] {
+            let genome_id = GenomeId(genome_id.to_string());
+            assert_eq!(genome_id.parse().map(|x| x.canonical), genome_id.to_canonical());
+        }
+
+        assert_eq!(GenomeId("GCA_123456789.256".to_string()).parse(), None);
+        assert_eq!(GenomeId("RS_GCA_123456789.1".to_string()).parse(), None);
+        assert_eq!(GenomeId("G123456789".to_string()).parse(), None);
+        assert_eq!(GenomeId("UBA12345".to_string()).parse(), None);
+    }
+
+    #[test]
+    fn test_split_db_prefix() {
+        let split = |x: &str| GenomeId(x.to_string()).split_db_prefix();
+        assert_eq!(split("RS_GCF_123456789.1"), (Some("RS_".to_string()), GenomeId("GCF_123456789.1".to_string())));
+        assert_eq!(split("GB_GCA_123456789.1"), (Some("GB_".to_string()), GenomeId("GCA_123456789.1".to_string())));
+        assert_eq!(split("GCA_123456789.1"), (None, GenomeId("GCA_123456789.1".to_string())));
+        assert_eq!(split("UBA12345"), (None, GenomeId("UBA12345".to_string())));
+        assert_eq!(split("rs_GCF_123456789.1"), (None, GenomeId("rs_GCF_123456789.1".to_string())));
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(GenomeId("GB_GCA_123456789.1".to_string()).kind(), AccessionKind::GenBank);
+        assert_eq!(GenomeId("GCA_123456789.1".to_string()).kind(), AccessionKind::GenBank);
+        assert_eq!(GenomeId("RS_GCF_123456789.1".to_string()).kind(), AccessionKind::RefSeq);
+        assert_eq!(GenomeId("GCF_123456789.10".to_string()).kind(), AccessionKind::RefSeq);
+        assert_eq!(GenomeId("G123456789".to_string()).kind(), AccessionKind::Canonical);
+        assert_eq!(GenomeId("UBA12345".to_string()).kind(), AccessionKind::Uba);
+        assert_eq!(GenomeId("U_12345".to_string()).kind(), AccessionKind::Uba);
+        assert_eq!(GenomeId("RS_GCA_123456789.1".to_string()).kind(), AccessionKind::Other);
+        assert_eq!(GenomeId("G12345678".to_string()).kind(), AccessionKind::Other);
+        assert_eq!(GenomeId("UBA".to_string()).kind(), AccessionKind::Other);
+        assert_eq!(GenomeId("something".to_string()).kind(), AccessionKind::Other);
+        assert_eq!(GenomeId("".to_string()).kind(), AccessionKind::Other);
+    }
+
+    #[test]
+    fn test_canonicalize_all() {
+        let ids = [
+            GenomeId("GB_GCA_123456789.1".to_string()),
+            GenomeId("GCF_123456789.2".to_string()),
+            GenomeId("UBA12345".to_string()),
+            GenomeId("UBA12345".to_string()),
+        ];
+        let result = canonicalize_all(ids.iter());
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[&ids[0]], Some(GenomeId("G123456789".to_string())));
+        assert_eq!(result[&ids[1]], Some(GenomeId("G123456789".to_string())));
+        assert_eq!(result[&ids[2]], None);
+        assert!(canonicalize_all(&[]).is_empty());
+    }
 }
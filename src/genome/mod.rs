@@ -1,2 +1,5 @@
 pub mod genome_id;
-pub mod fasta_file;
\ No newline at end of file
+pub mod fasta_file;
+pub mod prodigal_gff;
+#[cfg(feature = "memmap2")]
+pub mod fasta_mmap;
@@ -0,0 +1,204 @@
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::error::{ProleError, ProleResult};
+use crate::util::io::open_reader;
+use crate::util::parse::read_lines;
+
+/// The number of tab separated columns in a GFF3 feature line.
+const GFF_COLUMNS: usize = 9;
+
+/// Wraps a GFF3 file produced by Prodigal (i.e. `-f gff`).
+///
+/// ## Example
+/// An example of the file would appear in the format as follows:
+///
+/// ```text
+/// ##gff-version  3
+/// # Sequence Data: seqnum=1;seqlen=2215172;seqhdr="NC_022084.1"
+/// NC_022084.1    Prodigal_v2.6.3    CDS    3    1040    132.4    +    0    ID=1_1;partial=10;start_type=Edge;...
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProdigalGff(pub Vec<GffFeature>);
+
+impl ProdigalGff {
+    /// Read the content from a [BufReader] and parse it into a [ProdigalGff].
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::genome::prodigal_gff::ProdigalGff;
+    ///
+    /// let content = "##gff-version  3\nc1\tProdigal_v2.6.3\tCDS\t3\t14\t13.2\t-\t0\tID=1_1\n";
+    /// let gff = ProdigalGff::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+    /// assert_eq!(gff.0[0].strand, -1);
+    /// ```
+    pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        let mut out = vec![];
+        for line in read_lines(buf) {
+            let line = line?;
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            out.push(GffFeature::from_string(&line)?);
+        }
+        Ok(Self(out))
+    }
+
+    /// Read the content from a [Path] and parse it into a [ProdigalGff], compressed files are
+    /// decompressed automatically (see [open_reader]).
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::genome::prodigal_gff::ProdigalGff;
+    ///
+    /// let gff = ProdigalGff::from_path(Path::new("/path/to/genes.gff.gz")).unwrap();
+    /// ```
+    pub fn from_path(path: &Path) -> ProleResult<Self> {
+        Self::from_bufreader(open_reader(path)?)
+    }
+
+    /// Returns an [Iterator] over the `CDS` features.
+    pub fn cds(&self) -> impl Iterator<Item=&GffFeature> {
+        self.0.iter().filter(|x| x.feature_type == "CDS")
+    }
+}
+
+
+/// A single feature (line) within a [ProdigalGff] file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GffFeature {
+    /// The ID of the sequence (contig) that the feature is located on.
+    pub seq_id: String,
+    /// The program that generated the feature.
+    pub source: String,
+    /// The type of the feature (e.g. `CDS`).
+    pub feature_type: String,
+    /// The start position of the feature (1-based, inclusive).
+    pub start: u64,
+    /// The end position of the feature (1-based, inclusive).
+    pub end: u64,
+    /// The score of the feature, if present.
+    pub score: Option<f64>,
+    /// The strand of the feature, `1` (forward), `-1` (reverse), or `0` (unknown).
+    pub strand: i8,
+    /// The phase of the feature, if present.
+    pub phase: Option<u8>,
+    /// The semicolon separated attributes of the feature.
+    pub attributes: String,
+}
+
+impl GffFeature {
+    /// Creates a new [GffFeature] from a line in a GFF3 file.
+    ///
+    /// ```
+    /// use prole::genome::prodigal_gff::GffFeature;
+    ///
+    /// let feature = GffFeature::from_string("c1\tProdigal_v2.6.3\tCDS\t3\t14\t13.2\t+\t0\tID=1_1").unwrap();
+    /// assert_eq!(feature.start, 3);
+    /// assert_eq!(feature.end, 14);
+    /// ```
+    pub fn from_string(string: &str) -> ProleResult<Self> {
+        let split = string.split('\t').collect::<Vec<&str>>();
+        if split.len() != GFF_COLUMNS {
+            return Err(ProleError::Exit(format!("Expected {} columns, got {}: {}", GFF_COLUMNS, split.len(), string)));
+        }
+        let strand = match split[6] {
+            "+" => 1,
+            "-" => -1,
+            "." | "?" => 0,
+            _ => return Err(ProleError::Exit(format!("Invalid strand '{}': {}", split[6], string))),
+        };
+        let start: u64 = split[3].parse().map_err(ProleError::ParseIntError)?;
+        let end: u64 = split[4].parse().map_err(ProleError::ParseIntError)?;
+        if start == 0 || start > end {
+            return Err(ProleError::Exit(format!("Invalid coordinates: {}", string)));
+        }
+        Ok(Self {
+            seq_id: split[0].to_string(),
+            source: split[1].to_string(),
+            feature_type: split[2].to_string(),
+            start,
+            end,
+            score: match split[5] {
+                "." => None,
+                x => Some(x.parse().map_err(ProleError::ParseFloatError)?),
+            },
+            strand,
+            phase: match split[7] {
+                "." => None,
+                x => Some(x.parse().map_err(ProleError::ParseIntError)?),
+            },
+            attributes: split[8].to_string(),
+        })
+    }
+
+    /// Returns the length of the feature.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn test_from_path_valid_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "##gff-version  3").unwrap();
+        writeln!(file, "# Sequence Data: seqnum=1;seqlen=2215172;seqhdr=\"NC_022084.1\"").unwrap();
+        writeln!(file, "NC_022084.1\tProdigal_v2.6.3\tCDS\t3\t1040\t132.4\t+\t0\tID=1_1;partial=10;start_type=Edge").unwrap();
+        writeln!(file, "NC_022084.1\tProdigal_v2.6.3\tCDS\t1037\t2419\t180.0\t-\t0\tID=1_2;partial=00;start_type=ATG").unwrap();
+        writeln!(file, "NC_022084.1\tProdigal_v2.6.3\ttRNA\t2500\t2570\t.\t.\t.\tID=1_3").unwrap();
+
+        let result = ProdigalGff::from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.0.len(), 3);
+        assert_eq!(result.cds().count(), 2);
+        assert_eq!(result.0[0].seq_id, "NC_022084.1");
+        assert_eq!(result.0[0].length(), 1038);
+        assert_eq!(result.0[0].score, Some(132.4));
+        assert_eq!(result.0[1].strand, -1);
+        assert_eq!(result.0[1].phase, Some(0));
+        assert_eq!(result.0[2].score, None);
+        assert_eq!(result.0[2].strand, 0);
+        assert_eq!(result.0[2].phase, None);
+    }
+
+    #[test]
+    fn test_from_path_gz() {
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(b"##gff-version  3\nc1\tProdigal_v2.6.3\tCDS\t3\t14\t1.0\t+\t0\tID=1_1\n").unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&e.finish().unwrap()).unwrap();
+
+        let result = ProdigalGff::from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0].seq_id, "c1");
+    }
+
+    #[test]
+    fn test_from_bufreader_crlf() {
+        // The final line is truncated after the `\r`, which BufRead::lines alone would keep
+        let content = "##gff-version  3\r\nc1\tProdigal_v2.6.3\tCDS\t3\t14\t1.0\t+\t0\tID=1_1\r\n\r\nc1\tProdigal_v2.6.3\tCDS\t20\t40\t1.0\t-\t0\tID=1_2\r";
+        let result = ProdigalGff::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+        assert_eq!(result.0.len(), 2);
+        assert_eq!(result.0[0].attributes, "ID=1_1");
+        assert_eq!(result.0[1].attributes, "ID=1_2");
+    }
+
+    #[test]
+    fn test_from_string_invalid() {
+        assert!(GffFeature::from_string("").is_err());
+        assert!(GffFeature::from_string("c1\tProdigal\tCDS\t3\t14\t1.0\t+\t0").is_err());
+        assert!(GffFeature::from_string("c1\tProdigal\tCDS\t14\t3\t1.0\t+\t0\tID=1").is_err());
+        assert!(GffFeature::from_string("c1\tProdigal\tCDS\t0\t3\t1.0\t+\t0\tID=1").is_err());
+        assert!(GffFeature::from_string("c1\tProdigal\tCDS\t3\t14\t1.0\tx\t0\tID=1").is_err());
+    }
+}
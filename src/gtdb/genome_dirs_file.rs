@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io;
 use std::path::{Path, PathBuf};
 
 use crate::error::{ProleError, ProleResult};
+use crate::genome::fasta_file::FastaFile;
 use crate::genome::genome_id::GenomeId;
+use crate::util::io::open_reader;
+use crate::util::parse::read_lines;
 
 /// This struct wraps the GTDB genome_dirs.tsv file.
 ///
@@ -14,6 +17,9 @@ use crate::genome::genome_id::GenomeId;
 /// GCA_934854545.1	/srv/db/gtdb/genomes/ncbi/release214/genbank/GCA/934/854/545/GCA_934854545.1_MTG236_bin.31.fa	G934854545
 /// GCA_934854535.1	/srv/db/gtdb/genomes/ncbi/release214/genbank/GCA/934/854/535/GCA_934854535.1_MTG234_bin.48.fa	G934854535
 /// ```
+// The example keeps the tabs of the default delimiter
+#[allow(clippy::tabs_in_doc_comments)]
+#[derive(Debug, Clone)]
 pub struct GenomeDirsFile(pub HashMap<GenomeId, PathBuf>);
 
 impl GenomeDirsFile {
@@ -27,24 +33,104 @@ impl GenomeDirsFile {
     /// let out = GenomeDirsFile::load(&path).unwrap();
     /// ```
     pub fn load(path: &Path) -> ProleResult<Self> {
+        Self::load_with_delimiter(path, '\t')
+    }
+
+    /// Load the [GenomeDirsFile] from the specified [Path], where the columns are separated by `delim`.
+    ///
+    /// The first column is the genome ID, and the last column (if there are three or more) is
+    /// the canonical ID, everything in between is the path. Therefore the path may contain
+    /// the delimiter, provided the canonical ID column is present.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::genome_dirs_file::GenomeDirsFile;
+    ///
+    /// let path = Path::new("/path/to/file.csv");
+    /// let out = GenomeDirsFile::load_with_delimiter(&path, ',').unwrap();
+    /// ```
+    pub fn load_with_delimiter(path: &Path, delim: char) -> ProleResult<Self> {
         let file = File::open(path).map_err(ProleError::IoError)?;
         let reader = io::BufReader::new(file);
 
         let mut out = HashMap::new();
-        for line in reader.lines() {
-            let line = line.map_err(ProleError::IoError)?;
-            let line_split = line.split('\t').collect::<Vec<&str>>();
-            let genome_id = GenomeId(line_split[0].to_string());
-            let genome_dir = PathBuf::from(line_split[1]);
-            out.insert(genome_id, genome_dir);
+        for line in read_lines(reader) {
+            let line = line?;
+            let (genome_id, rest) = line.split_once(delim)
+                .ok_or_else(|| ProleError::Exit(format!("Expected at least 2 columns: {}", line)))?;
+            let genome_dir = rest.rsplit_once(delim).map_or(rest, |(genome_dir, _canonical_id)| genome_dir);
+            out.insert(GenomeId(genome_id.to_string()), PathBuf::from(genome_dir));
         }
         Ok(Self(out))
     }
 
+    /// Load the [GenomeDirsFile] from the specified [Path], resolving any relative paths in the
+    /// file against `base`. Absolute paths are unchanged.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::genome_dirs_file::GenomeDirsFile;
+    ///
+    /// let path = Path::new("/path/to/file");
+    /// let out = GenomeDirsFile::load_with_base(&path, Path::new("/srv/db/gtdb")).unwrap();
+    /// ```
+    pub fn load_with_base(path: &Path, base: &Path) -> ProleResult<Self> {
+        let mut out = Self::load(path)?;
+        for genome_dir in out.0.values_mut() {
+            if genome_dir.is_relative() {
+                *genome_dir = base.join(&genome_dir);
+            }
+        }
+        Ok(out)
+    }
+
     /// Returns the path for the specified [GenomeId].
     pub fn get_path(&self, genome_id: &GenomeId) -> Option<&PathBuf> {
         self.0.get(genome_id)
     }
+
+    /// Returns the path for the specified [GenomeId], or [ProleError::NotFound] if it is missing.
+    pub fn get_path_or_err(&self, genome_id: &GenomeId) -> ProleResult<&PathBuf> {
+        self.get_path(genome_id).ok_or_else(|| ProleError::NotFound(format!("genome directory for {}", genome_id.0)))
+    }
+
+    /// Load the [FastaFile] at the path for the specified [GenomeId], compressed files are
+    /// decompressed automatically (see [open_reader]). A [ProleError::NotFound] is returned if
+    /// the [GenomeId] is not present, or the file does not exist.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::genome::genome_id::GenomeId;
+    /// use prole::gtdb::genome_dirs_file::GenomeDirsFile;
+    ///
+    /// let genome_dirs = GenomeDirsFile::load(Path::new("/path/to/genome_dirs.tsv")).unwrap();
+    /// let fasta = genome_dirs.load_fasta(&GenomeId("GCA_934854595.1".to_string())).unwrap();
+    /// ```
+    pub fn load_fasta(&self, genome_id: &GenomeId) -> ProleResult<FastaFile> {
+        let path = self.get_path_or_err(genome_id)?;
+        if !path.is_file() {
+            return Err(ProleError::NotFound(format!("FASTA file for {}: {}", genome_id.0, path.display())));
+        }
+        FastaFile::from_bufreader(open_reader(path)?)
+    }
+
+    /// Returns a new [GenomeDirsFile] containing only the entries whose path exists on disk.
+    pub fn existing(&self) -> GenomeDirsFile {
+        GenomeDirsFile(self.0.iter()
+            .filter(|(_, path)| path.exists())
+            .map(|(genome_id, path)| (genome_id.clone(), path.clone()))
+            .collect())
+    }
+
+    /// Returns the [GenomeId]s (sorted) whose path does not exist on disk.
+    pub fn missing(&self) -> Vec<&GenomeId> {
+        let mut out: Vec<&GenomeId> = self.0.iter()
+            .filter(|(_, path)| !path.exists())
+            .map(|(genome_id, _)| genome_id)
+            .collect();
+        out.sort();
+        out
+    }
 }
 
 
@@ -70,4 +156,80 @@ mod tests {
         assert_eq!(result.get_path(&GenomeId("GCA_934854545.1".to_string())), Some(PathBuf::from("/tmp/b/b")).as_ref());
         assert_eq!(result.get_path(&GenomeId("GCA_934854535.1".to_string())), Some(PathBuf::from("/c")).as_ref());
     }
+
+    #[test]
+    fn test_get_path_or_err() {
+        let result = GenomeDirsFile(HashMap::from([(GenomeId("G1".to_string()), PathBuf::from("/tmp/a"))]));
+        assert_eq!(result.get_path_or_err(&GenomeId("G1".to_string())).unwrap(), &PathBuf::from("/tmp/a"));
+        match result.get_path_or_err(&GenomeId("G2".to_string())) {
+            Err(ProleError::NotFound(msg)) => assert!(msg.contains("G2")),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_fasta() {
+        let dir = tempfile::tempdir().unwrap();
+        let fasta_path = dir.path().join("G1.fna.gz");
+        let mut writer = crate::util::io::FileWriter::create(&fasta_path).unwrap();
+        writeln!(writer, ">contig_1 desc\nACGT\n>contig_2\nGG").unwrap();
+        writer.finish().unwrap();
+
+        let dirs_path = dir.path().join("genome_dirs.tsv");
+        std::fs::write(&dirs_path, format!("G1\t{}\tG000000001\nG2\t{}\tG000000002\n", fasta_path.display(), dir.path().join("missing.fna").display())).unwrap();
+        let genome_dirs = GenomeDirsFile::load(&dirs_path).unwrap();
+
+        let fasta = genome_dirs.load_fasta(&GenomeId("G1".to_string())).unwrap();
+        assert_eq!(fasta.get_sequence("contig_1"), Some("ACGT"));
+        assert_eq!(fasta.get_sequence("contig_2"), Some("GG"));
+        assert!(matches!(genome_dirs.load_fasta(&GenomeId("G2".to_string())), Err(ProleError::NotFound(_))));
+        assert!(matches!(genome_dirs.load_fasta(&GenomeId("G3".to_string())), Err(ProleError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_load_with_delimiter() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "GCA_934854595.1,/tmp/a,G934854595").unwrap();
+        writeln!(file, "GCA_934854545.1,/tmp/b,c/genome.fna,G934854545").unwrap();
+        writeln!(file, "GCA_934854535.1,/c").unwrap();
+
+        let result = GenomeDirsFile::load_with_delimiter(&file.into_temp_path(), ',').unwrap();
+        assert_eq!(result.0.len(), 3);
+        assert_eq!(result.get_path(&GenomeId("GCA_934854595.1".to_string())), Some(&PathBuf::from("/tmp/a")));
+        assert_eq!(result.get_path(&GenomeId("GCA_934854545.1".to_string())), Some(&PathBuf::from("/tmp/b,c/genome.fna")));
+        assert_eq!(result.get_path(&GenomeId("GCA_934854535.1".to_string())), Some(&PathBuf::from("/c")));
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "GCA_934854595.1").unwrap();
+        assert!(GenomeDirsFile::load_with_delimiter(&file.into_temp_path(), ',').is_err());
+    }
+
+    #[test]
+    fn test_load_with_base() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "GCA_934854595.1\t/tmp/a\tG934854595").unwrap();
+        writeln!(file, "GCA_934854545.1\tgenomes/b/b.fna\tG934854545").unwrap();
+
+        let result = GenomeDirsFile::load_with_base(&file.into_temp_path(), Path::new("/srv/release")).unwrap();
+        assert_eq!(result.get_path(&GenomeId("GCA_934854595.1".to_string())), Some(&PathBuf::from("/tmp/a")));
+        assert_eq!(result.get_path(&GenomeId("GCA_934854545.1".to_string())), Some(&PathBuf::from("/srv/release/genomes/b/b.fna")));
+    }
+
+    #[test]
+    fn test_existing_missing() {
+        let present = NamedTempFile::new().unwrap();
+        let mut map = HashMap::new();
+        map.insert(GenomeId("GCA_000000001.1".to_string()), present.path().to_path_buf());
+        map.insert(GenomeId("GCA_000000003.1".to_string()), PathBuf::from("/does/not/exist/b.fna"));
+        map.insert(GenomeId("GCA_000000002.1".to_string()), PathBuf::from("/does/not/exist/a.fna"));
+        let genome_dirs = GenomeDirsFile(map);
+
+        let existing = genome_dirs.existing();
+        assert_eq!(existing.0.len(), 1);
+        assert!(existing.get_path(&GenomeId("GCA_000000001.1".to_string())).is_some());
+        assert_eq!(genome_dirs.missing(), vec![
+            &GenomeId("GCA_000000002.1".to_string()),
+            &GenomeId("GCA_000000003.1".to_string()),
+        ]);
+    }
 }
@@ -1,16 +1,23 @@
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufReader, Write};
+use std::num::ParseFloatError;
 use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use log::debug;
 
 use crate::error::{ProleError, ProleResult};
 use crate::genome::genome_id::GenomeId;
 use crate::gtdb::taxonomy::Taxonomy;
+use crate::util::io::FileWriter;
+use crate::util::parse::{FromLine, read_lines, split_quoted};
+
+fn parse_float<T: std::str::FromStr<Err=ParseFloatError>>(value: &str) -> ProleResult<T> {
+    value.parse().map_err(ProleError::ParseFloatError)
+}
 
-// fn parse_float<T: std::str::FromStr<Err=ParseFloatError>>(value: &str) -> ProleResult<T> {
-//     value.parse().map_err(ProleError::ParseFloatError)
-// }
-//
 // fn parse_opt_float<T: std::str::FromStr<Err=ParseFloatError>>(value: &str) -> ProleResult<Option<T>> {
 //     if value == "none" {
 //         Ok(None)
@@ -39,12 +46,54 @@ use crate::gtdb::taxonomy::Taxonomy;
 //     }
 // }
 
+/// The NCBI assembly level of a genome.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NcbiAssemblyLevel {
+    CompleteGenome,
+    Chromosome,
+    Scaffold,
+    Contig,
+}
+
+impl FromStr for NcbiAssemblyLevel {
+    type Err = ProleError;
+
+    /// Parse the assembly level using the spelling in the GTDB metadata file.
+    ///
+    /// ```
+    /// use prole::gtdb::metadata_r214::NcbiAssemblyLevel;
+    ///
+    /// let level: NcbiAssemblyLevel = "Complete Genome".parse().unwrap();
+    /// assert_eq!(level, NcbiAssemblyLevel::CompleteGenome);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Complete Genome" => Ok(Self::CompleteGenome),
+            "Chromosome" => Ok(Self::Chromosome),
+            "Scaffold" => Ok(Self::Scaffold),
+            "Contig" => Ok(Self::Contig),
+            _ => Err(ProleError::Exit(format!("Unknown NCBI assembly level: {}", s))),
+        }
+    }
+}
+
+/// The MIMAG quality tier of a genome.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum MimagQuality {
+    High,
+    Medium,
+    Low,
+}
+
 /// A row within the [GtdbMetadataR214] file.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GtdbMetadataR214Row {
     pub accession: GenomeId,
     // pub ambiguous_bases: usize,
-    // pub checkm_completeness: f32,
-    // pub checkm_contamination: f32,
+    pub checkm_completeness: f32,
+    pub checkm_contamination: f32,
     // pub checkm_marker_count: usize,
     // pub checkm_marker_lineage: String,
     // pub checkm_marker_set_count: usize,
@@ -55,7 +104,7 @@ pub struct GtdbMetadataR214Row {
     // pub gc_count: usize,
     // pub gc_percentage: f64,
     // pub genome_size: usize,
-    // pub gtdb_genome_representative: GenomeId,
+    pub gtdb_genome_representative: GenomeId,
     pub gtdb_representative: bool,
     pub gtdb_taxonomy: Taxonomy,
     // pub gtdb_type_designation_ncbi_taxa: String,
@@ -81,12 +130,12 @@ pub struct GtdbMetadataR214Row {
     // pub lsu_silva_23s_taxonomy: Option<String>,
     // pub mean_contig_length: usize,
     // pub mean_scaffold_length: usize,
-    // pub mimag_high_quality: bool,
-    // pub mimag_low_quality: bool,
-    // pub mimag_medium_quality: bool,
+    pub mimag_high_quality: bool,
+    pub mimag_low_quality: bool,
+    pub mimag_medium_quality: bool,
     // pub n50_contigs: usize,
     // pub n50_scaffolds: usize,
-    // pub ncbi_assembly_level: String,
+    pub ncbi_assembly_level: NcbiAssemblyLevel,
     // pub ncbi_assembly_name: String,
     // pub ncbi_assembly_type: Option<String>,
     // pub ncbi_bioproject: String,
@@ -151,19 +200,34 @@ pub struct GtdbMetadataR214Row {
     // pub trna_aa_count: usize,
     // pub trna_count: usize,
     // pub trna_selenocysteine_count: usize,
+    /// Any columns after the 110 R214 columns (e.g. custom annotations), in their original order.
+    pub extra: Vec<String>,
 }
 
 impl GtdbMetadataR214Row {
     pub fn from_string(string: &str) -> ProleResult<Self> {
-        let split = string.split('\t').collect::<Vec<&str>>();
-        if split.len() != 110 {
-            return Err(ProleError::Exit(format!("Expected 110 columns, got {}", split.len())));
+        Self::from_string_with_delimiter(string, '\t')
+    }
+
+    /// Parse a row where the columns are separated by `delim`. At least the 110 R214 columns are
+    /// required, any additional trailing columns are stored in `extra`.
+    ///
+    /// Some columns contain free text (e.g. `ncbi_submitter`), therefore if `delim` is not a tab,
+    /// a column containing the delimiter must be quoted (see [split_quoted]).
+    pub fn from_string_with_delimiter(string: &str, delim: char) -> ProleResult<Self> {
+        let quoted = if delim == '\t' { None } else { Some(split_quoted(string, delim)?) };
+        let split: Vec<&str> = match &quoted {
+            Some(columns) => columns.iter().map(String::as_str).collect(),
+            None => string.split(delim).collect(),
+        };
+        if split.len() < 110 {
+            return Err(ProleError::Exit(format!("Expected at least 110 columns, got {}", split.len())));
         }
         let out = Self {
             accession: GenomeId(split[0].to_string()),
             // ambiguous_bases: parse_int(split[1])?,
-            // checkm_completeness: parse_float(split[2])?,
-            // checkm_contamination: parse_float(split[3])?,
+            checkm_completeness: parse_float(split[2])?,
+            checkm_contamination: parse_float(split[3])?,
             // checkm_marker_count: parse_int(split[4])?,
             // checkm_marker_lineage: split[5].to_string(),
             // checkm_marker_set_count: parse_int(split[6])?,
@@ -174,7 +238,7 @@ impl GtdbMetadataR214Row {
             // gc_count: parse_int(split[11])?,
             // gc_percentage: parse_float(split[12])?,
             // genome_size: parse_int(split[13])?,
-            // gtdb_genome_representative: GenomeId(split[14].to_string()),
+            gtdb_genome_representative: GenomeId(split[14].to_string()),
             gtdb_representative: split[15] == "t",
             gtdb_taxonomy: Taxonomy::from_string(split[16])?,
             // gtdb_type_designation_ncbi_taxa: split[17].to_string(),
@@ -200,12 +264,12 @@ impl GtdbMetadataR214Row {
             // lsu_silva_23s_taxonomy: parse_opt_string(split[37]),
             // mean_contig_length: parse_int(split[38])?,
             // mean_scaffold_length: parse_int(split[39])?,
-            // mimag_high_quality: split[40] == "t",
-            // mimag_low_quality: split[41] == "t",
-            // mimag_medium_quality: split[42] == "t",
+            mimag_high_quality: split[40] == "t",
+            mimag_low_quality: split[41] == "t",
+            mimag_medium_quality: split[42] == "t",
             // n50_contigs: parse_int(split[43])?,
             // n50_scaffolds: parse_int(split[44])?,
-            // ncbi_assembly_level: split[45].to_string(),
+            ncbi_assembly_level: split[45].parse()?,
             // ncbi_assembly_name: split[46].to_string(),
             // ncbi_assembly_type: parse_opt_string(split[47]),
             // ncbi_bioproject: split[48].to_string(),
@@ -270,30 +334,124 @@ impl GtdbMetadataR214Row {
             // trna_aa_count: parse_int(split[107])?,
             // trna_count: parse_int(split[108])?,
             // trna_selenocysteine_count: parse_int(split[109])?,
+            extra: split[110..].iter().map(|x| x.to_string()).collect(),
         };
         Ok(out)
     }
+
+    /// Returns the [MimagQuality] of this genome using the `mimag_*_quality` columns.
+    ///
+    /// If more than one column is set, the highest quality tier takes precedence
+    /// (i.e. high, then medium, then low). If none are set, the genome is considered low quality.
+    pub fn mimag_quality(&self) -> MimagQuality {
+        if self.mimag_high_quality {
+            MimagQuality::High
+        } else if self.mimag_medium_quality {
+            MimagQuality::Medium
+        } else {
+            MimagQuality::Low
+        }
+    }
+
+    /// Returns the [MimagQuality] of this genome computed from the CheckM completeness and
+    /// contamination, rather than the `mimag_*_quality` columns (which also consider the
+    /// rRNA and tRNA genes):
+    ///
+    /// * High: completeness ≥ 90 and contamination ≤ 5.
+    /// * Medium: completeness ≥ 50 and contamination ≤ 10.
+    /// * Low: otherwise.
+    pub fn checkm_mimag_quality(&self) -> MimagQuality {
+        let (completeness, contamination) = (self.checkm_completeness, self.checkm_contamination);
+        if completeness >= 90.0 && contamination <= 5.0 {
+            MimagQuality::High
+        } else if completeness >= 50.0 && contamination <= 10.0 {
+            MimagQuality::Medium
+        } else {
+            MimagQuality::Low
+        }
+    }
+}
+
+
+impl FromLine for GtdbMetadataR214Row {
+    fn from_line(line: &str) -> ProleResult<Self> {
+        Self::from_string(line)
+    }
+
+    /// Skips empty lines and the header.
+    fn is_skipped(line: &str) -> bool {
+        line.is_empty() || line.starts_with("accession\tambiguous_bases")
+    }
+}
+
+
+/// Split a line of the metadata file into its columns, see [GtdbMetadataR214Row::from_string_with_delimiter].
+fn split_columns(line: &str, delim: char) -> ProleResult<Vec<String>> {
+    if delim == '\t' {
+        Ok(line.split(delim).map(|x| x.to_string()).collect())
+    } else {
+        split_quoted(line, delim)
+    }
+}
+
+
+/// The original (tab separated) lines of a metadata file, see [GtdbMetadataR214::from_path_keep_raw].
+#[derive(Debug, Clone)]
+struct RawLines {
+    header: Option<String>,
+    rows: HashMap<GenomeId, String>,
 }
 
 
 /// The GTDB R214 metadata file.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GtdbMetadataR214 {
     pub rows: HashMap<GenomeId, GtdbMetadataR214Row>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw: Option<RawLines>,
 }
 
 impl GtdbMetadataR214 {
     pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        Self::from_bufreader_with_delimiter(buf, '\t')
+    }
+
+    /// Read the content from a [BufReader] where the columns are separated by `delim`.
+    pub fn from_bufreader_with_delimiter<T: std::io::Read>(buf: BufReader<T>, delim: char) -> ProleResult<Self> {
+        Self::read(buf, delim, false)
+    }
+
+    /// As [GtdbMetadataR214::from_bufreader_with_delimiter], but also keeps the original lines
+    /// so that they can be written by [GtdbMetadataR214::write_subset].
+    pub fn from_bufreader_keep_raw<T: std::io::Read>(buf: BufReader<T>, delim: char) -> ProleResult<Self> {
+        Self::read(buf, delim, true)
+    }
+
+    fn read<T: std::io::Read>(buf: BufReader<T>, delim: char, keep_raw: bool) -> ProleResult<Self> {
+        let header = format!("accession{}", delim);
         let mut out: HashMap<GenomeId, GtdbMetadataR214Row> = HashMap::new();
-        for line in buf.lines() {
-            let line = line.map_err(ProleError::IoError)?;
-            if line.starts_with("accession\tambiguous_bases") || line.is_empty() {
+        let mut raw = RawLines { header: None, rows: HashMap::new() };
+        for line in read_lines(buf) {
+            let line = line?;
+            if line.starts_with(&header) {
+                if keep_raw {
+                    raw.header = Some(split_columns(&line, delim)?.join("\t"));
+                }
+                continue;
+            }
+            if line.is_empty() {
                 continue;
             }
-            let row = GtdbMetadataR214Row::from_string(&line)?;
+            let row = GtdbMetadataR214Row::from_string_with_delimiter(&line, delim)?;
+            if keep_raw {
+                raw.rows.insert(row.accession.clone(), split_columns(&line, delim)?.join("\t"));
+            }
             out.insert(row.accession.clone(), row);
         }
         Ok(Self {
-            rows: out
+            rows: out,
+            raw: if keep_raw { Some(raw) } else { None },
         })
     }
 
@@ -302,6 +460,159 @@ impl GtdbMetadataR214 {
         let reader = BufReader::new(file);
         Self::from_bufreader(reader)
     }
+
+    /// Read the content from a [Path] where the columns are separated by `delim`, if `delim` is
+    /// not a tab then any column containing it must be quoted (see [split_quoted]).
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::metadata_r214::GtdbMetadataR214;
+    ///
+    /// let metadata = GtdbMetadataR214::from_path_with_delimiter(Path::new("/path/to/file.csv"), ',').unwrap();
+    /// ```
+    pub fn from_path_with_delimiter(path: &Path, delim: char) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(file);
+        Self::from_bufreader_with_delimiter(reader, delim)
+    }
+
+    /// As [GtdbMetadataR214::from_path_with_delimiter], but also keeps the original lines so
+    /// that they can be written by [GtdbMetadataR214::write_subset]. This roughly doubles the
+    /// memory required, and the lines are not kept by [GtdbMetadataR214::load_cached].
+    pub fn from_path_keep_raw(path: &Path, delim: char) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(file);
+        Self::from_bufreader_keep_raw(reader, delim)
+    }
+
+    /// Write the header (if present) and the rows for `ids` (in the order given) to a tab
+    /// separated file at [Path], the output is gz compressed if the extension is `.gz`.
+    /// The columns are written in the same order as they were read.
+    ///
+    /// The metadata must have been read with [GtdbMetadataR214::from_path_keep_raw].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::genome::genome_id::GenomeId;
+    /// use prole::gtdb::metadata_r214::GtdbMetadataR214;
+    ///
+    /// let metadata = GtdbMetadataR214::from_path_keep_raw(Path::new("/path/to/bac120_metadata.tsv"), '\t').unwrap();
+    /// let ids = vec![GenomeId("RS_GCF_000246985.2".to_string())];
+    /// metadata.write_subset(&ids, Path::new("/path/to/subset.tsv")).unwrap();
+    /// ```
+    pub fn write_subset(&self, ids: &[GenomeId], path: &Path) -> ProleResult<()> {
+        let raw = self.raw.as_ref()
+            .ok_or_else(|| ProleError::Exit("The original lines were not kept, see from_path_keep_raw".to_string()))?;
+        let rows = ids.iter()
+            .map(|id| raw.rows.get(id).ok_or_else(|| ProleError::Exit(format!("Missing metadata for: {}", id.0))))
+            .collect::<ProleResult<Vec<&String>>>()?;
+        let mut writer = FileWriter::create(path)?;
+        if let Some(header) = &raw.header {
+            writeln!(writer, "{}", header).map_err(ProleError::IoError)?;
+        }
+        for row in rows {
+            writeln!(writer, "{}", row).map_err(ProleError::IoError)?;
+        }
+        writer.finish()
+    }
+
+    /// Returns the row for the specified [GenomeId].
+    pub fn get(&self, genome_id: &GenomeId) -> Option<&GtdbMetadataR214Row> {
+        self.rows.get(genome_id)
+    }
+
+    /// Returns the row for the specified [GenomeId], or [ProleError::NotFound] if it is missing.
+    pub fn get_or_err(&self, genome_id: &GenomeId) -> ProleResult<&GtdbMetadataR214Row> {
+        self.get(genome_id).ok_or_else(|| ProleError::NotFound(format!("metadata for {}", genome_id.0)))
+    }
+
+    /// Returns an [Iterator] over the rows, sorted by [GenomeId].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::metadata_r214::GtdbMetadataR214;
+    ///
+    /// let metadata = GtdbMetadataR214::from_path(Path::new("/path/to/bac120_metadata.tsv")).unwrap();
+    /// for (genome_id, row) in metadata.iter_sorted() {
+    ///     println!("{}\t{}", genome_id.0, row.gtdb_representative);
+    /// }
+    /// ```
+    pub fn iter_sorted(&self) -> impl Iterator<Item=(&GenomeId, &GtdbMetadataR214Row)> {
+        let mut rows: Vec<(&GenomeId, &GtdbMetadataR214Row)> = self.rows.iter().collect();
+        rows.sort_by_key(|(genome_id, _)| *genome_id);
+        rows.into_iter()
+    }
+
+    /// Returns the GTDB species clusters, i.e. each representative genome mapped to the genomes
+    /// assigned to it (including itself) via the `gtdb_genome_representative` column. The
+    /// members of each cluster are sorted by [GenomeId].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::metadata_r214::GtdbMetadataR214;
+    ///
+    /// let metadata = GtdbMetadataR214::from_path(Path::new("/path/to/bac120_metadata.tsv")).unwrap();
+    /// for (representative, members) in metadata.cluster_members() {
+    ///     println!("{}\t{}", representative.0, members.len());
+    /// }
+    /// ```
+    pub fn cluster_members(&self) -> HashMap<GenomeId, Vec<GenomeId>> {
+        let mut out: HashMap<GenomeId, Vec<GenomeId>> = HashMap::new();
+        for row in self.rows.values() {
+            out.entry(row.gtdb_genome_representative.clone()).or_default().push(row.accession.clone());
+        }
+        for members in out.values_mut() {
+            members.sort();
+        }
+        out
+    }
+
+    /// Load the metadata file at [Path], using a cached copy in `cache_dir` if one exists.
+    /// Otherwise, the file is parsed and the cache is written for subsequent calls.
+    ///
+    /// The cache is a [bincode] serialized [GtdbMetadataR214] named
+    /// `{file_name}.{size}.{mtime}.v{crate_version}.bin`, where `size` and `mtime` (nanoseconds
+    /// since the epoch) are taken from the source file. Therefore, modifying the source file
+    /// (or upgrading this crate) will cause it to be re-parsed. Stale cache files are not removed.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::metadata_r214::GtdbMetadataR214;
+    ///
+    /// let metadata = GtdbMetadataR214::load_cached(Path::new("/path/to/bac120_metadata.tsv"), Path::new("/tmp/cache")).unwrap();
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn load_cached(path: &Path, cache_dir: &Path) -> ProleResult<Self> {
+        let cache_path = cache_dir.join(Self::cache_name(path)?);
+        if let Ok(file) = File::open(&cache_path) {
+            match bincode::deserialize_from(BufReader::new(file)) {
+                Ok(out) => return Ok(out),
+                Err(e) => debug!("Ignoring unreadable cache {}: {}", cache_path.display(), e),
+            }
+        }
+
+        let out = Self::from_path(path)?;
+        std::fs::create_dir_all(cache_dir).map_err(ProleError::IoError)?;
+        let tmp_file = tempfile::NamedTempFile::new_in(cache_dir).map_err(ProleError::IoError)?;
+        let mut writer = std::io::BufWriter::new(tmp_file.as_file());
+        bincode::serialize_into(&mut writer, &out)
+            .map_err(|e| ProleError::Exit(format!("Unable to write cache: {}", e)))?;
+        writer.flush().map_err(ProleError::IoError)?;
+        drop(writer);
+        tmp_file.persist(&cache_path).map_err(|e| ProleError::IoError(e.error))?;
+        Ok(out)
+    }
+
+    /// Returns the file name of the cache for the metadata file at [Path], see [GtdbMetadataR214::load_cached].
+    #[cfg(feature = "serde")]
+    fn cache_name(path: &Path) -> ProleResult<String> {
+        let metadata = std::fs::metadata(path).map_err(ProleError::IoError)?;
+        let mtime = metadata.modified().map_err(ProleError::IoError)?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos());
+        let file_name = path.file_name().map_or("metadata".to_string(), |x| x.to_string_lossy().to_string());
+        Ok(format!("{}.{}.{}.v{}.bin", file_name, metadata.len(), mtime, env!("CARGO_PKG_VERSION")))
+    }
 }
 
 
@@ -309,9 +620,205 @@ impl GtdbMetadataR214 {
 mod tests {
     use super::*;
 
+    const ROW: &str = "RS_GCF_000246985.2\t44\t99.5\t0.5\t299\tp__Euryarchaeota (UID4)\t202\t0\t2014456\t90.93903317665627\t1\t954455\t43.08802922449628\t2215172\tRS_GCF_024054535.1\tf\td__Archaea;p__Methanobacteriota_B;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus_A;s__Thermococcus_A alcaliphilus\ttype strain of species\tLPSN\tf\t1\t1\t2215172\t2215172\t2215172\t1\t3020\tNC_022084.1\t2215172\t2\t103\tNC_022084.1\t3020\t5561\t0\t99.901\tAKID01000054.18410.21433\tArchaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus sp. PK\t2215172\t2215172\tt\tf\tf\t2215172\t2215172\tComplete Genome\tASM24698v3\tna\tPRJNA224116\tSAMN02603679\tnone\tnone\tnone\t2013-08-13\tGCA_000246985.3\tnone\tfull\tnone\tnone\tnone\t1\t0\tThermococcus litoralis DSM 5473\t2402\trepresentative genome\t4\t1\t1\t2215172\t2215172\t2215172\t2013/08/13\t0\t2265\t1\tDSM 5473\tNew England Biolabs, Inc.\t523849\td__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis\td__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis;x__Thermococcus litoralis DSM 5473\t0\t2215172\t11\t46\tassembly from type material\t2215172\t0\tnone\t2497\t1\t2215172\t1\tnone\tnone\tnone\tnone\tnone\tnone\t1485\tNC_022084.1\t1485\t2743\t0\t100\tCP006670.774259.775759\tArchaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus litoralis DSM 5473\t0\t19\t45\t0";
+
+    /// Returns the example row with the columns at the specified indices replaced.
+    fn row_with(values: &[(usize, &str)]) -> GtdbMetadataR214Row {
+        let mut split = ROW.split('\t').collect::<Vec<&str>>();
+        for &(idx, value) in values {
+            split[idx] = value;
+        }
+        GtdbMetadataR214Row::from_string(&split.join("\t")).unwrap()
+    }
+
     #[test]
     fn test_from_string() {
-        let result = GtdbMetadataR214Row::from_string("RS_GCF_000246985.2\t44\t99.5\t0.5\t299\tp__Euryarchaeota (UID4)\t202\t0\t2014456\t90.93903317665627\t1\t954455\t43.08802922449628\t2215172\tRS_GCF_024054535.1\tf\td__Archaea;p__Methanobacteriota_B;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus_A;s__Thermococcus_A alcaliphilus\ttype strain of species\tLPSN\tf\t1\t1\t2215172\t2215172\t2215172\t1\t3020\tNC_022084.1\t2215172\t2\t103\tNC_022084.1\t3020\t5561\t0\t99.901\tAKID01000054.18410.21433\tArchaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus sp. PK\t2215172\t2215172\tt\tf\tf\t2215172\t2215172\tComplete Genome\tASM24698v3\tna\tPRJNA224116\tSAMN02603679\tnone\tnone\tnone\t2013-08-13\tGCA_000246985.3\tnone\tfull\tnone\tnone\tnone\t1\t0\tThermococcus litoralis DSM 5473\t2402\trepresentative genome\t4\t1\t1\t2215172\t2215172\t2215172\t2013/08/13\t0\t2265\t1\tDSM 5473\tNew England Biolabs, Inc.\t523849\td__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis\td__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis;x__Thermococcus litoralis DSM 5473\t0\t2215172\t11\t46\tassembly from type material\t2215172\t0\tnone\t2497\t1\t2215172\t1\tnone\tnone\tnone\tnone\tnone\tnone\t1485\tNC_022084.1\t1485\t2743\t0\t100\tCP006670.774259.775759\tArchaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus litoralis DSM 5473\t0\t19\t45\t0");
+        let result = GtdbMetadataR214Row::from_string(ROW);
         assert!(result.is_ok());
+        let result = result.unwrap();
+        assert_eq!(result.ncbi_assembly_level, NcbiAssemblyLevel::CompleteGenome);
+    }
+
+    #[test]
+    fn test_from_string_extra_columns() {
+        assert_eq!(ROW.split('\t').count(), 110);
+        assert!(GtdbMetadataR214Row::from_string(ROW).unwrap().extra.is_empty());
+
+        let row = GtdbMetadataR214Row::from_string(&format!("{}\tcustom 1\t", ROW)).unwrap();
+        assert_eq!(row.extra, vec!["custom 1", ""]);
+        assert_eq!(row.ncbi_assembly_level, NcbiAssemblyLevel::CompleteGenome);
+
+        // A known column containing the delimiter is not split into extra columns
+        let quoted = |x: &str| if x.contains(',') { format!("\"{}\"", x) } else { x.to_string() };
+        let csv = ROW.split('\t').map(quoted).collect::<Vec<String>>().join(",");
+        assert!(GtdbMetadataR214Row::from_string_with_delimiter(&csv, ',').unwrap().extra.is_empty());
+        let row = GtdbMetadataR214Row::from_string_with_delimiter(&format!("{},\"custom, 1\"", csv), ',').unwrap();
+        assert_eq!(row.extra, vec!["custom, 1"]);
+
+        let truncated = ROW.rsplit_once('\t').unwrap().0;
+        assert!(GtdbMetadataR214Row::from_string(truncated).is_err());
+    }
+
+    #[test]
+    fn test_from_bufreader_with_delimiter() {
+        let content = format!("accession|ambiguous_bases|...\n{}\n", ROW.replace('\t', "|"));
+        let result = GtdbMetadataR214::from_bufreader_with_delimiter(BufReader::new(content.as_bytes()), '|').unwrap();
+        assert_eq!(result.rows.len(), 1);
+        let row = &result.rows[&GenomeId("RS_GCF_000246985.2".to_string())];
+        assert_eq!(row.ncbi_assembly_level, NcbiAssemblyLevel::CompleteGenome);
+
+        // Columns containing a comma must be quoted, e.g. the submitter
+        let csv = ROW.split('\t').map(|x| if x.contains(',') { format!("\"{}\"", x) } else { x.to_string() }).collect::<Vec<String>>().join(",");
+        let content = format!("accession,ambiguous_bases,...\n{}\n", csv);
+        let result = GtdbMetadataR214::from_bufreader_with_delimiter(BufReader::new(content.as_bytes()), ',').unwrap();
+        let row = &result.rows[&GenomeId("RS_GCF_000246985.2".to_string())];
+        assert_eq!(row.ncbi_assembly_level, NcbiAssemblyLevel::CompleteGenome);
+        assert!(GtdbMetadataR214Row::from_string_with_delimiter(&csv.replacen("Inc.\"", "Inc.", 1), ',').is_err());
+
+        // The final column may contain the delimiter
+        let row = GtdbMetadataR214Row::from_string_with_delimiter(&format!("{}|extra", ROW.replace('\t', "|")), '|');
+        assert!(row.is_ok());
+        assert!(GtdbMetadataR214Row::from_string_with_delimiter(ROW, '|').is_err());
+    }
+
+    #[test]
+    fn test_write_subset() {
+        let header = (0..110).map(|i| if i == 0 { "accession".to_string() } else if i == 1 { "ambiguous_bases".to_string() } else { format!("col{}", i) }).collect::<Vec<String>>().join("\t");
+        let row_b = ROW.replacen("RS_GCF_000246985.2", "GB_GCA_000000001.1", 1);
+        let row_c = ROW.replacen("RS_GCF_000246985.2", "GB_GCA_000000002.1", 1);
+        let content = format!("{}\n{}\n{}\n{}\n", header, ROW, row_b, row_c);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("subset.tsv");
+        let ids = vec![GenomeId("GB_GCA_000000002.1".to_string()), GenomeId("RS_GCF_000246985.2".to_string())];
+
+        // The original lines must be kept
+        let metadata = GtdbMetadataR214::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+        assert!(metadata.write_subset(&ids, &path).is_err());
+
+        let metadata = GtdbMetadataR214::from_bufreader_keep_raw(BufReader::new(content.as_bytes()), '\t').unwrap();
+        metadata.write_subset(&ids, &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n{}\n{}\n", header, row_c, ROW));
+
+        let reloaded = GtdbMetadataR214::from_path(&path).unwrap();
+        assert_eq!(reloaded.rows.len(), 2);
+
+        assert!(metadata.write_subset(&[GenomeId("missing".to_string())], &path).is_err());
+
+        // Any header starting with the accession column is kept, and the delimiter is converted
+        let custom_header = header.replacen("ambiguous_bases", "custom", 1);
+        let content = format!("{}\n{}\n", custom_header.replace('\t', "|"), ROW.replace('\t', "|"));
+        let metadata = GtdbMetadataR214::from_bufreader_keep_raw(BufReader::new(content.as_bytes()), '|').unwrap();
+        metadata.write_subset(&ids[1..], &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n{}\n", custom_header, ROW));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_load_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metadata.tsv");
+        let cache_dir = dir.path().join("cache");
+        std::fs::write(&path, format!("{}\n", ROW)).unwrap();
+        let n_cache_files = || std::fs::read_dir(&cache_dir).unwrap().count();
+
+        // The first load writes the cache
+        let metadata = GtdbMetadataR214::load_cached(&path, &cache_dir).unwrap();
+        assert_eq!(metadata.rows.len(), 1);
+        assert_eq!(n_cache_files(), 1);
+
+        // The second load reads the cache, replace it to confirm it's used
+        let cache_path = std::fs::read_dir(&cache_dir).unwrap().next().unwrap().unwrap().path();
+        let empty = GtdbMetadataR214 { rows: HashMap::new(), raw: None };
+        std::fs::write(&cache_path, bincode::serialize(&empty).unwrap()).unwrap();
+        assert!(GtdbMetadataR214::load_cached(&path, &cache_dir).unwrap().rows.is_empty());
+
+        // Modifying the source forces a re-parse
+        let row_b = ROW.replacen("RS_GCF_000246985.2", "GB_GCA_000000001.1", 1);
+        std::fs::write(&path, format!("{}\n{}\n", ROW, row_b)).unwrap();
+        let metadata = GtdbMetadataR214::load_cached(&path, &cache_dir).unwrap();
+        assert_eq!(metadata.rows.len(), 2);
+        assert_eq!(n_cache_files(), 2);
+        assert_eq!(GtdbMetadataR214::load_cached(&path, &cache_dir).unwrap().rows.len(), 2);
+
+        // An unreadable cache is ignored
+        std::fs::write(&path, format!("{}\n", ROW)).unwrap();
+        let cache_path = cache_dir.join(GtdbMetadataR214::cache_name(&path).unwrap());
+        std::fs::write(&cache_path, b"invalid").unwrap();
+        assert_eq!(GtdbMetadataR214::load_cached(&path, &cache_dir).unwrap().rows.len(), 1);
+    }
+
+    #[test]
+    fn test_iter_sorted() {
+        let mut rows = HashMap::new();
+        for accession in ["RS_GCF_000000003.1", "GB_GCA_000000002.1", "RS_GCF_000000001.1"] {
+            rows.insert(GenomeId(accession.to_string()), row_with(&[(0, accession)]));
+        }
+        let metadata = GtdbMetadataR214 { rows, raw: None };
+        let accessions: Vec<&str> = metadata.iter_sorted().map(|(genome_id, row)| {
+            assert_eq!(genome_id, &row.accession);
+            genome_id.0.as_str()
+        }).collect();
+        assert_eq!(accessions, vec!["GB_GCA_000000002.1", "RS_GCF_000000001.1", "RS_GCF_000000003.1"]);
+    }
+
+    #[test]
+    fn test_cluster_members() {
+        let mut rows = HashMap::new();
+        for (accession, representative) in [("G3", "G1"), ("G1", "G1"), ("G2", "G1"), ("G4", "G4")] {
+            rows.insert(GenomeId(accession.to_string()), row_with(&[(0, accession), (14, representative)]));
+        }
+        let metadata = GtdbMetadataR214 { rows, raw: None };
+        let clusters = metadata.cluster_members();
+        let ids = |x: &[&str]| x.iter().map(|x| GenomeId(x.to_string())).collect::<Vec<GenomeId>>();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[&GenomeId("G1".to_string())], ids(&["G1", "G2", "G3"]));
+        assert_eq!(clusters[&GenomeId("G4".to_string())], ids(&["G4"]));
+    }
+
+    #[test]
+    fn test_from_line() {
+        let content = format!("accession\tambiguous_bases\t...\n{}\n\n", ROW);
+        let rows: Vec<GtdbMetadataR214Row> = crate::util::parse::parse_lines(BufReader::new(content.as_bytes())).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].accession, GenomeId("RS_GCF_000246985.2".to_string()));
+    }
+
+    #[test]
+    fn test_ncbi_assembly_level_from_str() {
+        assert_eq!("Complete Genome".parse::<NcbiAssemblyLevel>().unwrap(), NcbiAssemblyLevel::CompleteGenome);
+        assert_eq!("Chromosome".parse::<NcbiAssemblyLevel>().unwrap(), NcbiAssemblyLevel::Chromosome);
+        assert_eq!("Scaffold".parse::<NcbiAssemblyLevel>().unwrap(), NcbiAssemblyLevel::Scaffold);
+        assert_eq!("Contig".parse::<NcbiAssemblyLevel>().unwrap(), NcbiAssemblyLevel::Contig);
+        assert!("CompleteGenome".parse::<NcbiAssemblyLevel>().is_err());
+        assert!("complete genome".parse::<NcbiAssemblyLevel>().is_err());
+        assert!("".parse::<NcbiAssemblyLevel>().is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_mimag_quality() {
+        assert_eq!(row_with(&[]).mimag_quality(), MimagQuality::High);
+        assert_eq!(row_with(&[(40, "f"), (41, "f"), (42, "t")]).mimag_quality(), MimagQuality::Medium);
+        assert_eq!(row_with(&[(40, "f"), (41, "t"), (42, "f")]).mimag_quality(), MimagQuality::Low);
+        assert_eq!(row_with(&[(40, "f"), (41, "f"), (42, "f")]).mimag_quality(), MimagQuality::Low);
+        assert_eq!(row_with(&[(40, "t"), (41, "t"), (42, "t")]).mimag_quality(), MimagQuality::High);
+        assert_eq!(row_with(&[(40, "f"), (41, "t"), (42, "t")]).mimag_quality(), MimagQuality::Medium);
+    }
+
+    #[test]
+    fn test_checkm_mimag_quality() {
+        let row = row_with(&[]);
+        assert_eq!(row.checkm_completeness, 99.5);
+        assert_eq!(row.checkm_contamination, 0.5);
+        assert_eq!(row.checkm_mimag_quality(), MimagQuality::High);
+
+        let quality = |completeness: &str, contamination: &str| row_with(&[(2, completeness), (3, contamination)]).checkm_mimag_quality();
+        assert_eq!(quality("90", "5"), MimagQuality::High);
+        assert_eq!(quality("89.99", "5"), MimagQuality::Medium);
+        assert_eq!(quality("90", "5.01"), MimagQuality::Medium);
+        assert_eq!(quality("50", "10"), MimagQuality::Medium);
+        assert_eq!(quality("49.99", "0"), MimagQuality::Low);
+        assert_eq!(quality("100", "10.01"), MimagQuality::Low);
+        assert!(GtdbMetadataR214Row::from_string(&ROW.replacen("\t99.5\t", "\tx\t", 1)).is_err());
+    }
+}
@@ -2,4 +2,6 @@ pub mod genome_dirs_file;
 pub mod taxonomy_rank;
 pub mod metadata_r214;
 pub mod taxonomy;
-pub mod taxon;
\ No newline at end of file
+pub mod taxon;
+pub mod taxonomy_file;
+pub mod set_ops;
@@ -0,0 +1,90 @@
+//! Set operations on [GenomeId]s that compare the canonical form of each accession
+//! (see [GenomeId::to_canonical]), so that e.g. `RS_GCF_123456789.1` matches `GCA_123456789.2`.
+//!
+//! Accessions without a canonical form (e.g. `UBA12345`) are compared as-is. The results are
+//! keyed by the canonical form, and the value is the representative accession from the first
+//! set. If several accessions in the first set share a canonical form, the lowest (by [Ord])
+//! is chosen as the representative so that the output is deterministic.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::genome::genome_id::GenomeId;
+
+/// Group the accessions by canonical form, choosing the lowest accession as the representative.
+fn by_canonical(ids: &HashSet<GenomeId>) -> HashMap<GenomeId, &GenomeId> {
+    let mut out: HashMap<GenomeId, &GenomeId> = HashMap::with_capacity(ids.len());
+    for genome_id in ids {
+        let canonical = genome_id.to_canonical().unwrap_or_else(|| genome_id.clone());
+        let representative = out.entry(canonical).or_insert(genome_id);
+        if genome_id < *representative {
+            *representative = genome_id;
+        }
+    }
+    out
+}
+
+/// Returns the genomes present in both `a` and `b`, comparing canonical forms.
+///
+/// ```
+/// use std::collections::HashSet;
+/// use prole::genome::genome_id::GenomeId;
+/// use prole::gtdb::set_ops::intersection;
+///
+/// let a = HashSet::from([GenomeId("RS_GCF_123456789.1".to_string()), GenomeId("UBA1".to_string())]);
+/// let b = HashSet::from([GenomeId("G123456789".to_string())]);
+/// let shared = intersection(&a, &b);
+/// assert_eq!(shared[&GenomeId("G123456789".to_string())], GenomeId("RS_GCF_123456789.1".to_string()));
+/// assert_eq!(shared.len(), 1);
+/// ```
+pub fn intersection(a: &HashSet<GenomeId>, b: &HashSet<GenomeId>) -> HashMap<GenomeId, GenomeId> {
+    let b = by_canonical(b);
+    by_canonical(a).into_iter()
+        .filter(|(canonical, _)| b.contains_key(canonical))
+        .map(|(canonical, representative)| (canonical, representative.clone()))
+        .collect()
+}
+
+/// Returns the genomes present in `a` but not in `b`, comparing canonical forms.
+pub fn difference(a: &HashSet<GenomeId>, b: &HashSet<GenomeId>) -> HashMap<GenomeId, GenomeId> {
+    let b = by_canonical(b);
+    by_canonical(a).into_iter()
+        .filter(|(canonical, _)| !b.contains_key(canonical))
+        .map(|(canonical, representative)| (canonical, representative.clone()))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ids: &[&str]) -> HashSet<GenomeId> {
+        ids.iter().map(|id| GenomeId(id.to_string())).collect()
+    }
+
+    fn gid(id: &str) -> GenomeId {
+        GenomeId(id.to_string())
+    }
+
+    #[test]
+    fn test_intersection_difference() {
+        let a = set(&["RS_GCF_000000001.1", "GCA_000000001.2", "GB_GCA_000000002.1", "UBA1", "UBA2"]);
+        let b = set(&["G000000001", "GCF_000000003.1", "UBA1"]);
+
+        let shared = intersection(&a, &b);
+        assert_eq!(shared, HashMap::from([
+            (gid("G000000001"), gid("GCA_000000001.2")),
+            (gid("UBA1"), gid("UBA1")),
+        ]));
+
+        let only_a = difference(&a, &b);
+        assert_eq!(only_a, HashMap::from([
+            (gid("G000000002"), gid("GB_GCA_000000002.1")),
+            (gid("UBA2"), gid("UBA2")),
+        ]));
+
+        let only_b = difference(&b, &a);
+        assert_eq!(only_b, HashMap::from([(gid("G000000003"), gid("GCF_000000003.1"))]));
+        assert!(intersection(&a, &HashSet::new()).is_empty());
+    }
+}
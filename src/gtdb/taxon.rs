@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+
+use crate::gtdb::taxonomy_rank::{TAXONOMY_RANKS, TaxonomyRank};
 
 /// A [Taxon] within the GTDB.
 ///
@@ -6,5 +9,173 @@
 ///
 /// let _ = Taxon("d__Bacteria".to_string());
 /// ```
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Taxon(pub String);
+
+impl Taxon {
+    /// Returns the [TaxonomyRank] implied by the prefix of this [Taxon], if it is recognised.
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// assert_eq!(Taxon("g__Thermococcus".to_string()).rank(), Some(TaxonomyRank::Genus));
+    /// assert_eq!(Taxon("Thermococcus".to_string()).rank(), None);
+    /// ```
+    pub fn rank(&self) -> Option<TaxonomyRank> {
+        self.0.split_once("__").and_then(|(prefix, _)| TaxonomyRank::from_prefix(prefix))
+    }
+
+    /// Returns the name of this [Taxon] without the rank prefix (if present).
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    ///
+    /// assert_eq!(Taxon("g__Thermococcus_A".to_string()).name(), "Thermococcus_A");
+    /// ```
+    pub fn name(&self) -> &str {
+        self.0.split_once("__").map_or(self.0.as_str(), |(_, name)| name)
+    }
+
+    /// Returns true if both taxa have the same name, ignoring the rank prefix (see [Taxon::name]).
+    /// This allows a bare name to be matched against a prefixed [Taxon].
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    ///
+    /// let taxon = Taxon("g__Escherichia".to_string());
+    /// assert!(taxon.name_eq(&Taxon("s__Escherichia".to_string())));
+    /// assert!(taxon.name_eq(&Taxon("Escherichia".to_string())));
+    /// assert_ne!(taxon, Taxon("s__Escherichia".to_string()));
+    /// ```
+    pub fn name_eq(&self, other: &Taxon) -> bool {
+        self.name() == other.name()
+    }
+
+    /// Returns the name of this [Taxon] without the rank prefix, or the GTDB alphabetic suffix
+    /// used to denote polyphyletic groups (i.e. a trailing `_` followed by uppercase letters).
+    ///
+    /// Species names (e.g. `s__Thermococcus_A alcaliphilus` or `s__Prochlorococcus marinus_A`)
+    /// are returned unchanged, other than the rank prefix.
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    ///
+    /// assert_eq!(Taxon("g__Thermococcus_A".to_string()).base_name(), "Thermococcus");
+    /// assert_eq!(Taxon("g__Thermococcus".to_string()).base_name(), "Thermococcus");
+    /// assert_eq!(Taxon("s__Prochlorococcus marinus_A".to_string()).base_name(), "Prochlorococcus marinus_A");
+    /// ```
+    pub fn base_name(&self) -> &str {
+        let name = self.name();
+        if self.rank() == Some(TaxonomyRank::Species) {
+            return name;
+        }
+        if let Some((base, suffix)) = name.rsplit_once('_') {
+            if !base.is_empty() && !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_uppercase()) {
+                return base;
+            }
+        }
+        name
+    }
+
+    /// Returns a normalized copy of this [Taxon] (the original is unchanged), for comparing
+    /// taxa whose formatting differs between files. The following is applied:
+    ///
+    /// 1. Leading and trailing whitespace is removed.
+    /// 2. Whitespace around the rank prefix separator is removed (e.g. `s__ E coli` to `s__E coli`).
+    /// 3. Each run of whitespace (spaces, tabs, etc.) is replaced by a single space.
+    ///
+    /// Underscores are not modified, as they are meaningful in GTDB names (e.g. `g__Thermococcus_A`).
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    ///
+    /// let taxon = Taxon(" s__Escherichia  coli ".to_string());
+    /// assert_eq!(taxon.normalized(), Taxon("s__Escherichia coli".to_string()));
+    /// ```
+    pub fn normalized(&self) -> Taxon {
+        let collapse = |string: &str| string.split_whitespace().collect::<Vec<&str>>().join(" ");
+        match self.0.split_once("__") {
+            Some((prefix, name)) => Taxon(format!("{}__{}", prefix.trim(), collapse(name))),
+            None => Taxon(collapse(&self.0)),
+        }
+    }
+}
+
+
+/// Taxa are ordered by [TaxonomyRank] (domain to species), then by name. Taxa without a
+/// recognised rank prefix are ordered last.
+impl Ord for Taxon {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |taxon: &Taxon| taxon.rank().map_or(TAXONOMY_RANKS.len(), |rank| rank.index());
+        key(self).cmp(&key(other))
+            .then_with(|| self.name().cmp(other.name()))
+            .then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for Taxon {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_name() {
+        assert_eq!(Taxon("g__Thermococcus_A".to_string()).base_name(), "Thermococcus");
+        assert_eq!(Taxon("p__Methanobacteriota_B".to_string()).base_name(), "Methanobacteriota");
+        assert_eq!(Taxon("p__Firmicutes_AQ".to_string()).base_name(), "Firmicutes");
+        assert_eq!(Taxon("g__Thermococcus".to_string()).base_name(), "Thermococcus");
+        assert_eq!(Taxon("s__Thermococcus_A alcaliphilus".to_string()).base_name(), "Thermococcus_A alcaliphilus");
+        assert_eq!(Taxon("s__Prochlorococcus marinus_A".to_string()).base_name(), "Prochlorococcus marinus_A");
+        assert_eq!(Taxon("g__JAAXYZ01_1".to_string()).base_name(), "JAAXYZ01_1");
+        assert_eq!(Taxon("g__Foo_bar".to_string()).base_name(), "Foo_bar");
+        assert_eq!(Taxon("g___A".to_string()).base_name(), "_A");
+        assert_eq!(Taxon("g__".to_string()).base_name(), "");
+        assert_eq!(Taxon("Thermococcus_A".to_string()).base_name(), "Thermococcus");
+    }
+
+    #[test]
+    fn test_normalized() {
+        assert_eq!(Taxon("s__Escherichia  coli".to_string()).normalized().0, "s__Escherichia coli");
+        assert_eq!(Taxon("  s__ Escherichia\tcoli_A \n".to_string()).normalized().0, "s__Escherichia coli_A");
+        assert_eq!(Taxon("g__Thermococcus_A".to_string()).normalized().0, "g__Thermococcus_A");
+        assert_eq!(Taxon(" g__ ".to_string()).normalized().0, "g__");
+        assert_eq!(Taxon("Escherichia   coli".to_string()).normalized().0, "Escherichia coli");
+
+        let taxon = Taxon("s__Escherichia  coli".to_string());
+        let _ = taxon.normalized();
+        assert_eq!(taxon.0, "s__Escherichia  coli");
+    }
+
+    #[test]
+    fn test_rank_name() {
+        assert_eq!(Taxon("s__Escherichia coli".to_string()).rank(), Some(TaxonomyRank::Species));
+        assert_eq!(Taxon("s__Escherichia coli".to_string()).name(), "Escherichia coli");
+        assert_eq!(Taxon("x__Foo".to_string()).rank(), None);
+        assert_eq!(Taxon("Foo".to_string()).name(), "Foo");
+    }
+
+    #[test]
+    fn test_name_eq() {
+        let taxon = |x: &str| Taxon(x.to_string());
+        assert!(taxon("g__Escherichia").name_eq(&taxon("s__Escherichia")));
+        assert!(taxon("Escherichia").name_eq(&taxon("g__Escherichia")));
+        assert!(!taxon("g__Escherichia").name_eq(&taxon("g__Escherichia_A")));
+        assert!(!taxon("g__Escherichia").name_eq(&taxon("s__Escherichia coli")));
+        assert_ne!(taxon("g__Escherichia"), taxon("s__Escherichia"));
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut taxa: Vec<Taxon> = ["g__B", "Unranked", "d__A", "g__A", "x__A", "s__A a"].iter().map(|x| Taxon(x.to_string())).collect();
+        taxa.sort();
+        let taxa: Vec<&str> = taxa.iter().map(|x| x.0.as_str()).collect();
+        assert_eq!(taxa, vec!["d__A", "g__A", "g__B", "s__A a", "x__A", "Unranked"]);
+    }
+}
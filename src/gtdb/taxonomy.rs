@@ -1,8 +1,12 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::error::{ProleError, ProleResult};
 use crate::gtdb::taxon::Taxon;
 use crate::gtdb::taxonomy_rank::TaxonomyRank;
 
 /// A 7-rank [Taxonomy] containing taxa ([Taxon]) for each [TaxonomyRank].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Taxonomy {
     pub domain: Taxon,
     pub phylum: Taxon,
@@ -40,6 +44,114 @@ impl Taxonomy {
         })
     }
 
+    /// Creates a [Taxonomy] struct from a taxonomy string, normalizing the format of each field
+    /// (see [Taxon::normalized]) and converting the rank prefix to lowercase, e.g. `D__ Bacteria`
+    /// becomes `d__Bacteria`. Fields without a rank prefix are only trimmed.
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let taxonomy = Taxonomy::from_string_normalized("D__ d1;p__p1;c__c1;o__o1;f__f1;g__g1;S__s1  s2").unwrap();
+    /// assert_eq!(taxonomy.get(&TaxonomyRank::Domain), &Taxon("d__d1".to_string()));
+    /// assert_eq!(taxonomy.get(&TaxonomyRank::Species), &Taxon("s__s1 s2".to_string()));
+    /// ```
+    pub fn from_string_normalized(string: &str) -> ProleResult<Self> {
+        let fields: Vec<String> = string.split(';')
+            .map(|field| {
+                let taxon = Taxon(field.to_string()).normalized();
+                match taxon.0.split_once("__") {
+                    Some((prefix, name)) => format!("{}__{}", prefix.to_lowercase(), name),
+                    None => taxon.0,
+                }
+            })
+            .collect();
+        Self::from_string(&fields.join(";"))
+    }
+
+    /// Creates a [Taxonomy] struct from a taxonomy string that may be missing ranks.
+    ///
+    /// Each field is assigned to a rank using its prefix (in any order, the last field is used if a
+    /// rank is repeated), empty fields are skipped, and any missing ranks are filled with an empty
+    /// placeholder (e.g. `s__`). An error is only returned if a prefix is not recognised.
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let taxonomy = Taxonomy::from_string_lenient("d__d1;p__p1;c__c1").unwrap();
+    /// assert_eq!(taxonomy.get(&TaxonomyRank::Class), &Taxon("c__c1".to_string()));
+    /// assert_eq!(taxonomy.get(&TaxonomyRank::Species), &Taxon("s__".to_string()));
+    /// ```
+    pub fn from_string_lenient(string: &str) -> ProleResult<Self> {
+        let mut taxa: [Option<Taxon>; 7] = Default::default();
+        for field in string.split(';').map(|field| field.trim()).filter(|field| !field.is_empty()) {
+            let rank = field.split_once("__")
+                .and_then(|(prefix, _)| TaxonomyRank::from_prefix(prefix))
+                .ok_or_else(|| ProleError::Exit(format!("Unrecognised rank prefix: {}", field)))?;
+            taxa[rank as usize] = Some(Taxon(field.to_string()));
+        }
+
+        let fill = |taxon: Option<Taxon>, rank: TaxonomyRank| {
+            taxon.unwrap_or_else(|| Taxon(format!("{}__", rank.prefix())))
+        };
+        let [domain, phylum, class, order, family, genus, species] = taxa;
+        Ok(Self {
+            domain: fill(domain, TaxonomyRank::Domain),
+            phylum: fill(phylum, TaxonomyRank::Phylum),
+            class: fill(class, TaxonomyRank::Class),
+            order: fill(order, TaxonomyRank::Order),
+            family: fill(family, TaxonomyRank::Family),
+            genus: fill(genus, TaxonomyRank::Genus),
+            species: fill(species, TaxonomyRank::Species),
+        })
+    }
+
+    /// Creates a [Taxonomy] struct from a 7-rank NCBI taxonomy string, where the fields may not
+    /// have rank prefixes (e.g. `Bacteria;Bacillota;...`). Each unprefixed field is given the
+    /// prefix of its rank, an error is returned if a field has the prefix of a different rank.
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let taxonomy = Taxonomy::from_ncbi_string("Archaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus litoralis").unwrap();
+    /// assert_eq!(taxonomy.get(&TaxonomyRank::Phylum), &Taxon("p__Euryarchaeota".to_string()));
+    /// ```
+    pub fn from_ncbi_string(string: &str) -> ProleResult<Self> {
+        let string_split = string.split(';').collect::<Vec<&str>>();
+        if string_split.len() != 7 {
+            return Err(ProleError::Exit(format!("Taxonomy string has {} fields, expected 7", string_split.len())));
+        }
+        let mut taxa = Vec::with_capacity(7);
+        for (field, rank) in string_split.iter().zip(TaxonomyRank::all()) {
+            let field = field.trim();
+            let prefix = rank.prefix();
+            let taxon = match field.split_once("__") {
+                Some((field_prefix, _)) if field_prefix == prefix => field.to_string(),
+                Some((field_prefix, _)) if TaxonomyRank::from_prefix(field_prefix).is_some() => {
+                    return Err(ProleError::Exit(format!("Unexpected rank prefix for {:?}: {}", rank, field)));
+                }
+                _ => format!("{}__{}", prefix, field),
+            };
+            taxa.push(Taxon(taxon));
+        }
+        let mut taxa = taxa.into_iter();
+        let mut next = || taxa.next().unwrap();
+        Ok(Self {
+            domain: next(),
+            phylum: next(),
+            class: next(),
+            order: next(),
+            family: next(),
+            genus: next(),
+            species: next(),
+        })
+    }
+
     /// Returns the [Taxon] for a given [TaxonomyRank].
     ///
     /// ```
@@ -61,6 +173,122 @@ impl Taxonomy {
             TaxonomyRank::Species => &self.species,
         }
     }
+
+    /// Returns true if both taxonomies contain the same taxa from the domain down to `rank`
+    /// (inclusive). Placeholder taxa (e.g. `g__`) are only equal to other placeholders.
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1").unwrap();
+    /// let b = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s2").unwrap();
+    /// assert!(a.equal_to_rank(&b, TaxonomyRank::Genus));
+    /// assert!(!a.equal_to_rank(&b, TaxonomyRank::Species));
+    /// ```
+    pub fn equal_to_rank(&self, other: &Taxonomy, rank: TaxonomyRank) -> bool {
+        rank.higher_ranks_inclusive().all(|rank| self.get(&rank) == other.get(&rank))
+    }
+
+    /// Returns each [TaxonomyRank] (from domain to species) where the taxa differ, along with
+    /// the [Taxon] from `self` (old) and `other` (new).
+    ///
+    /// ```
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let old = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1").unwrap();
+    /// let new = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s2").unwrap();
+    /// assert_eq!(old.diff(&new), vec![(TaxonomyRank::Species, Taxon("s__s1".to_string()), Taxon("s__s2".to_string()))]);
+    /// ```
+    pub fn diff(&self, other: &Taxonomy) -> Vec<(TaxonomyRank, Taxon, Taxon)> {
+        TaxonomyRank::all()
+            .filter(|rank| self.get(rank) != other.get(rank))
+            .map(|rank| (rank, self.get(&rank).clone(), other.get(&rank).clone()))
+            .collect()
+    }
+
+    /// Returns a key for grouping identical lineages across files whose formatting differs.
+    ///
+    /// Each [Taxon] is normalized (see [Taxon::normalized]) and given the prefix of its
+    /// [TaxonomyRank] if it has none, then the taxa are joined by `;` without surrounding
+    /// whitespace. An error is returned if a [Taxon] has the prefix of a different rank.
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy::Taxonomy;
+    ///
+    /// let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__g1 s1").unwrap();
+    /// let b = Taxonomy::from_string("d__d1; p__p1; c__c1; o__o1; f__f1; g__ g1; s__g1  s1").unwrap();
+    /// assert_ne!(a, b);
+    /// assert_eq!(a.canonical_key().unwrap(), b.canonical_key().unwrap());
+    /// assert_eq!(a.canonical_key().unwrap(), "d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__g1 s1");
+    /// ```
+    pub fn canonical_key(&self) -> ProleResult<String> {
+        let mut taxa = Vec::with_capacity(7);
+        for rank in TaxonomyRank::all() {
+            let taxon = self.get(&rank).normalized();
+            if let Some((prefix, _)) = taxon.0.split_once("__") {
+                if prefix != rank.prefix() {
+                    return Err(ProleError::Exit(format!("Taxon has the wrong prefix for {:?}: {}", rank, taxon.0)));
+                }
+            }
+            taxa.push(format!("{}__{}", rank.prefix(), taxon.name()));
+        }
+        Ok(taxa.join(";"))
+    }
+}
+
+
+/// Count the number of distinct taxa at each [TaxonomyRank] (all ranks are present, even if the
+/// count is 0). Placeholder taxa (e.g. `g__`) are not counted.
+///
+/// ```
+/// use prole::gtdb::taxonomy::{count_distinct_taxa, Taxonomy};
+/// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+///
+/// let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1").unwrap();
+/// let b = Taxonomy::from_string("d__d1;p__p1;c__c2;o__o2;f__f2;g__g2;s__s2").unwrap();
+/// let counts = count_distinct_taxa(&[a, b]);
+/// assert_eq!(counts[&TaxonomyRank::Phylum], 1);
+/// assert_eq!(counts[&TaxonomyRank::Class], 2);
+/// ```
+pub fn count_distinct_taxa<'a, I: IntoIterator<Item=&'a Taxonomy>>(taxonomies: I) -> HashMap<TaxonomyRank, usize> {
+    let mut seen: HashMap<TaxonomyRank, HashSet<&Taxon>> = TaxonomyRank::all().map(|rank| (rank, HashSet::new())).collect();
+    for taxonomy in taxonomies {
+        for rank in TaxonomyRank::all() {
+            let taxon = taxonomy.get(&rank);
+            if !taxon.name().is_empty() {
+                seen.entry(rank).or_default().insert(taxon);
+            }
+        }
+    }
+    seen.into_iter().map(|(rank, taxa)| (rank, taxa.len())).collect()
+}
+
+/// Parses a taxonomy string of arbitrary depth and with arbitrary rank prefixes into a vector
+/// of (prefix, name) pairs, e.g. the `ncbi_taxonomy_unfiltered` metadata column which may contain
+/// additional `x__` ranks.
+///
+/// ```
+/// use prole::gtdb::taxonomy::parse_prefixed_lineage;
+///
+/// let lineage = parse_prefixed_lineage("d__Archaea;s__Thermococcus litoralis;x__Thermococcus litoralis DSM 5473").unwrap();
+/// assert_eq!(lineage[2], ("x".to_string(), "Thermococcus litoralis DSM 5473".to_string()));
+/// ```
+pub fn parse_prefixed_lineage(string: &str) -> ProleResult<Vec<(String, String)>> {
+    if string.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    string.split(';')
+        .map(|field| {
+            let field = field.trim();
+            match field.split_once("__") {
+                Some((prefix, name)) if !prefix.is_empty() => Ok((prefix.to_string(), name.trim().to_string())),
+                _ => Err(ProleError::Exit(format!("Missing rank prefix: {}", field))),
+            }
+        })
+        .collect()
 }
 
 
@@ -95,4 +323,162 @@ mod tests {
         assert_eq!(result.get(&TaxonomyRank::Genus).0, "g__g1");
         assert_eq!(result.get(&TaxonomyRank::Species).0, "s__s1 s2");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_from_string_lenient() {
+        let result = Taxonomy::from_string_lenient("d__d1; p__p1; c__c1").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Domain).0, "d__d1");
+        assert_eq!(result.get(&TaxonomyRank::Phylum).0, "p__p1");
+        assert_eq!(result.get(&TaxonomyRank::Class).0, "c__c1");
+        assert_eq!(result.get(&TaxonomyRank::Order).0, "o__");
+        assert_eq!(result.get(&TaxonomyRank::Family).0, "f__");
+        assert_eq!(result.get(&TaxonomyRank::Genus).0, "g__");
+        assert_eq!(result.get(&TaxonomyRank::Species).0, "s__");
+
+        let result = Taxonomy::from_string_lenient("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1 s2").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Species).0, "s__s1 s2");
+
+        let result = Taxonomy::from_string_lenient("d__d1;p__p1;g__g1").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Class).0, "c__");
+        assert_eq!(result.get(&TaxonomyRank::Genus).0, "g__g1");
+
+        // Ranks are assigned by prefix, empty fields are skipped, and the last repeat is used
+        let result = Taxonomy::from_string_lenient("d__d1;;c__c1;p__p1; ;d__d2;").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Domain).0, "d__d2");
+        assert_eq!(result.get(&TaxonomyRank::Phylum).0, "p__p1");
+        assert_eq!(result.get(&TaxonomyRank::Class).0, "c__c1");
+        assert_eq!(result.get(&TaxonomyRank::Order).0, "o__");
+
+        let result = Taxonomy::from_string_lenient("").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Domain).0, "d__");
+    }
+
+    #[test]
+    fn test_from_string_lenient_invalid() {
+        assert!(Taxonomy::from_string_lenient("d__d1;x__x1").is_err());
+        assert!(Taxonomy::from_string_lenient("d__d1;Bacteria").is_err());
+    }
+
+    #[test]
+    fn test_from_ncbi_string() {
+        let result = Taxonomy::from_ncbi_string("Archaea;Euryarchaeota;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus litoralis").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Domain).0, "d__Archaea");
+        assert_eq!(result.get(&TaxonomyRank::Phylum).0, "p__Euryarchaeota");
+        assert_eq!(result.get(&TaxonomyRank::Class).0, "c__Thermococci");
+        assert_eq!(result.get(&TaxonomyRank::Order).0, "o__Thermococcales");
+        assert_eq!(result.get(&TaxonomyRank::Family).0, "f__Thermococcaceae");
+        assert_eq!(result.get(&TaxonomyRank::Genus).0, "g__Thermococcus");
+        assert_eq!(result.get(&TaxonomyRank::Species).0, "s__Thermococcus litoralis");
+
+        // The ncbi_taxonomy column of the GTDB metadata is already prefixed
+        let result = Taxonomy::from_ncbi_string("d__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Domain).0, "d__Archaea");
+        assert_eq!(result.get(&TaxonomyRank::Species).0, "s__Thermococcus litoralis");
+
+        let result = Taxonomy::from_ncbi_string("Bacteria;;;;;;").unwrap();
+        assert_eq!(result.get(&TaxonomyRank::Phylum).0, "p__");
+
+        assert!(Taxonomy::from_ncbi_string("Archaea;Euryarchaeota").is_err());
+        assert!(Taxonomy::from_ncbi_string("Archaea;c__Thermococci;Thermococci;Thermococcales;Thermococcaceae;Thermococcus;Thermococcus litoralis").is_err());
+    }
+
+    #[test]
+    fn test_equal_to_rank() {
+        let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1").unwrap();
+        let b = Taxonomy::from_string("d__d1;p__p1;c__c2;o__o1;f__f1;g__g1;s__s1").unwrap();
+        assert!(a.equal_to_rank(&b, TaxonomyRank::Domain));
+        assert!(a.equal_to_rank(&b, TaxonomyRank::Phylum));
+        assert!(!a.equal_to_rank(&b, TaxonomyRank::Class));
+        assert!(!a.equal_to_rank(&b, TaxonomyRank::Species));
+        assert!(a.equal_to_rank(&a, TaxonomyRank::Species));
+
+        let placeholder = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__;s__").unwrap();
+        assert!(a.equal_to_rank(&placeholder, TaxonomyRank::Family));
+        assert!(!a.equal_to_rank(&placeholder, TaxonomyRank::Genus));
+        assert!(placeholder.equal_to_rank(&placeholder, TaxonomyRank::Species));
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1").unwrap();
+        let b = Taxonomy::from_string("d__d1;p__p1;c__c2;o__o1;f__f1;g__g1;s__s2").unwrap();
+        assert_eq!(a.diff(&b), vec![
+            (TaxonomyRank::Class, Taxon("c__c1".to_string()), Taxon("c__c2".to_string())),
+            (TaxonomyRank::Species, Taxon("s__s1".to_string()), Taxon("s__s2".to_string())),
+        ]);
+        assert!(a.diff(&a).is_empty());
+    }
+
+    #[test]
+    fn test_from_string_normalized() {
+        let result = Taxonomy::from_string_normalized(" D__Bacteria ; P__ Bacillota;c__Bacilli ;o__\tLactobacillales;f__Streptococcaceae;G __Streptococcus;s__Streptococcus   pyogenes\n").unwrap();
+        assert_eq!(result, Taxonomy::from_string("d__Bacteria;p__Bacillota;c__Bacilli;o__Lactobacillales;f__Streptococcaceae;g__Streptococcus;s__Streptococcus pyogenes").unwrap());
+
+        let result = Taxonomy::from_string_normalized("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1; s1").unwrap();
+        assert_eq!(result.species.0, "s1");
+        assert!(Taxonomy::from_string_normalized("d__d1;p__p1").is_err());
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let a = Taxonomy::from_string("d__d1;p__p1;c__c1;o__o1;f__f1;g__g1_A;s__g1_A s1").unwrap();
+        let b = Taxonomy::from_string(" d__d1 ;p__p1;c__ c1;o__o1;f__f1;g__g1_A;s__g1_A\ts1").unwrap();
+        let mut c = a.clone();
+        c.phylum = Taxon("p1".to_string());
+        let key = "d__d1;p__p1;c__c1;o__o1;f__f1;g__g1_A;s__g1_A s1";
+        assert_eq!(a.canonical_key().unwrap(), key);
+        assert_eq!(b.canonical_key().unwrap(), key);
+        assert_eq!(c.canonical_key().unwrap(), key);
+
+        let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for taxonomy in [&a, &b, &c] {
+            *counts.entry(taxonomy.canonical_key().unwrap()).or_default() += 1;
+        }
+        assert_eq!(counts[key], 3);
+
+        let placeholder = Taxonomy::from_string_lenient("d__d1").unwrap();
+        assert_eq!(placeholder.canonical_key().unwrap(), "d__d1;p__;c__;o__;f__;g__;s__");
+
+        let mut wrong_prefix = a.clone();
+        wrong_prefix.genus = Taxon("f__g1_A".to_string());
+        assert!(wrong_prefix.canonical_key().is_err());
+    }
+
+    #[test]
+    fn test_count_distinct_taxa() {
+        let taxonomies: Vec<Taxonomy> = [
+            "d__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1",
+            "d__d1;p__p1;c__c2;o__o2;f__f2;g__g2;s__s2",
+            "d__d1;p__p1;c__c3;o__o2;f__f3;g__;s__",
+        ].iter().map(|x| Taxonomy::from_string(x).unwrap()).collect();
+        let counts = count_distinct_taxa(taxonomies.iter());
+        let expected = [1, 1, 3, 2, 3, 2, 2];
+        for (rank, expected) in TaxonomyRank::all().zip(expected) {
+            assert_eq!(counts[&rank], expected, "{:?}", rank);
+        }
+
+        let counts = count_distinct_taxa(std::iter::empty());
+        assert_eq!(counts.len(), 7);
+        assert!(counts.values().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn test_parse_prefixed_lineage() {
+        let result = parse_prefixed_lineage("d__Archaea;p__Euryarchaeota;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus;s__Thermococcus litoralis;x__Thermococcus litoralis DSM 5473").unwrap();
+        assert_eq!(result.len(), 8);
+        assert_eq!(result[0], ("d".to_string(), "Archaea".to_string()));
+        assert_eq!(result[6], ("s".to_string(), "Thermococcus litoralis".to_string()));
+        assert_eq!(result[7], ("x".to_string(), "Thermococcus litoralis DSM 5473".to_string()));
+
+        let result = parse_prefixed_lineage("d__Bacteria; x__; x__strain").unwrap();
+        assert_eq!(result, vec![
+            ("d".to_string(), "Bacteria".to_string()),
+            ("x".to_string(), "".to_string()),
+            ("x".to_string(), "strain".to_string()),
+        ]);
+
+        assert!(parse_prefixed_lineage("").unwrap().is_empty());
+        assert!(parse_prefixed_lineage("d__Bacteria;Firmicutes").is_err());
+        assert!(parse_prefixed_lineage("d__Bacteria;__Firmicutes").is_err());
+    }
+}
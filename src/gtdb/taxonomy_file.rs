@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::error::{ProleError, ProleResult};
+use crate::genome::genome_id::GenomeId;
+use crate::gtdb::taxon::Taxon;
+use crate::gtdb::taxonomy::Taxonomy;
+use crate::gtdb::taxonomy_rank::TaxonomyRank;
+use crate::util::parse::read_lines;
+
+/// This struct wraps a GTDB taxonomy file (e.g. `bac120_taxonomy.tsv`).
+///
+/// An example of the content is as follows:
+/// ```text
+/// RS_GCF_000246985.2    d__Archaea;p__Methanobacteriota_B;c__Thermococci;o__Thermococcales;f__Thermococcaceae;g__Thermococcus_A;s__Thermococcus_A alcaliphilus
+/// ```
+#[derive(Debug, Clone)]
+pub struct TaxonomyFile(pub HashMap<GenomeId, Taxonomy>);
+
+impl TaxonomyFile {
+    /// Read the content from a [BufReader] and parse it into a [TaxonomyFile].
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::genome::genome_id::GenomeId;
+    /// use prole::gtdb::taxonomy_file::TaxonomyFile;
+    ///
+    /// let content = "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1\n";
+    /// let file = TaxonomyFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+    /// assert!(file.get(&GenomeId("G1".to_string())).is_some());
+    /// ```
+    pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        let mut out = HashMap::new();
+        for line in read_lines(buf) {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let (genome_id, taxonomy) = line.split_once('\t')
+                .ok_or_else(|| ProleError::Exit(format!("Expected 2 columns: {}", line)))?;
+            let genome_id = GenomeId(genome_id.to_string());
+            if out.contains_key(&genome_id) {
+                return Err(ProleError::Exit(format!("Duplicate ID found in taxonomy file: {}", genome_id.0)));
+            }
+            out.insert(genome_id, Taxonomy::from_string(taxonomy)?);
+        }
+        Ok(Self(out))
+    }
+
+    /// Read the content from a [Path] and parse it into a [TaxonomyFile].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::taxonomy_file::TaxonomyFile;
+    ///
+    /// let file = TaxonomyFile::from_path(Path::new("/path/to/bac120_taxonomy.tsv")).unwrap();
+    /// ```
+    pub fn from_path(path: &Path) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(file);
+        Self::from_bufreader(reader)
+    }
+
+    /// Read the content from a gz compressed file at [Path] and parse it into a [TaxonomyFile].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::gtdb::taxonomy_file::TaxonomyFile;
+    ///
+    /// let file = TaxonomyFile::from_path_gz(Path::new("/path/to/bac120_taxonomy.tsv.gz")).unwrap();
+    /// ```
+    pub fn from_path_gz(path: &Path) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(GzDecoder::new(file));
+        Self::from_bufreader(reader)
+    }
+
+    /// Returns the [Taxonomy] for the specified [GenomeId].
+    pub fn get(&self, genome_id: &GenomeId) -> Option<&Taxonomy> {
+        self.0.get(genome_id)
+    }
+
+    /// Build a [TaxonomyTree] from the lineages in this file.
+    ///
+    /// Placeholder taxa (e.g. `g__`) are not added to the tree, instead a genome is attached to
+    /// its lowest named taxon. An error is returned if a taxon has a different parent in two
+    /// lineages (e.g. the same genus in two families).
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::gtdb::taxonomy_file::TaxonomyFile;
+    ///
+    /// let content = "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1\nG2\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s2\n";
+    /// let tree = TaxonomyFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap().to_tree().unwrap();
+    /// assert_eq!(tree.children_of(&Taxon("g__g1".to_string())).len(), 2);
+    /// assert_eq!(tree.leaves_under(&Taxon("p__p1".to_string())).len(), 2);
+    /// ```
+    pub fn to_tree(&self) -> ProleResult<TaxonomyTree> {
+        let mut genome_ids: Vec<&GenomeId> = self.0.keys().collect();
+        genome_ids.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut tree = TaxonomyTree::default();
+        for genome_id in genome_ids {
+            tree.insert(genome_id, &self.0[genome_id])?;
+        }
+        tree.roots.sort_by(|a, b| a.0.cmp(&b.0));
+        for children in tree.children.values_mut() {
+            children.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        Ok(tree)
+    }
+}
+
+
+/// A tree of the [Taxon]s in a [TaxonomyFile], where each [Taxon] may have [GenomeId]s
+/// directly assigned to it (typically only species). See [TaxonomyFile::to_tree].
+#[derive(Debug, Clone, Default)]
+pub struct TaxonomyTree {
+    parents: HashMap<Taxon, Option<Taxon>>,
+    children: HashMap<Taxon, Vec<Taxon>>,
+    genomes: HashMap<Taxon, Vec<GenomeId>>,
+    roots: Vec<Taxon>,
+}
+
+impl TaxonomyTree {
+    /// Add the lineage of a genome to the tree.
+    fn insert(&mut self, genome_id: &GenomeId, taxonomy: &Taxonomy) -> ProleResult<()> {
+        let mut parent: Option<Taxon> = None;
+        for rank in TaxonomyRank::all() {
+            let taxon = taxonomy.get(&rank);
+            if taxon.0.ends_with("__") {
+                continue;
+            }
+            match self.parents.get(taxon) {
+                Some(existing) if existing != &parent => {
+                    let fmt = |x: &Option<Taxon>| x.as_ref().map_or("root".to_string(), |x| x.0.clone());
+                    return Err(ProleError::Exit(format!("Conflicting parents for {}: {} and {}", taxon.0, fmt(existing), fmt(&parent))));
+                }
+                Some(_) => {}
+                None => {
+                    self.parents.insert(taxon.clone(), parent.clone());
+                    match &parent {
+                        Some(parent) => self.children.entry(parent.clone()).or_default().push(taxon.clone()),
+                        None => self.roots.push(taxon.clone()),
+                    }
+                }
+            }
+            parent = Some(taxon.clone());
+        }
+        if let Some(parent) = parent {
+            self.genomes.entry(parent).or_default().push(genome_id.clone());
+        }
+        Ok(())
+    }
+
+    /// Returns the top-level [Taxon]s in the tree (typically the domains), sorted by name.
+    pub fn roots(&self) -> &[Taxon] {
+        &self.roots
+    }
+
+    /// Returns the parent of `taxon`, or [None] if it is a root or not in the tree.
+    pub fn parent_of(&self, taxon: &Taxon) -> Option<&Taxon> {
+        self.parents.get(taxon).and_then(|x| x.as_ref())
+    }
+
+    /// Returns the direct children of `taxon`, sorted by name.
+    pub fn children_of(&self, taxon: &Taxon) -> &[Taxon] {
+        self.children.get(taxon).map_or(&[], |x| x.as_slice())
+    }
+
+    /// Returns all [GenomeId]s assigned to `taxon`, or any of its descendants, sorted by ID.
+    pub fn leaves_under(&self, taxon: &Taxon) -> Vec<&GenomeId> {
+        let mut out = Vec::new();
+        let mut queue = vec![taxon];
+        while let Some(cur) = queue.pop() {
+            if let Some(genomes) = self.genomes.get(cur) {
+                out.extend(genomes.iter());
+            }
+            queue.extend(self.children_of(cur).iter());
+        }
+        out.sort_by(|a, b| a.0.cmp(&b.0));
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn taxonomy_file(lines: &[&str]) -> TaxonomyFile {
+        let mut file = NamedTempFile::new().unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        TaxonomyFile::from_path(&file.into_temp_path()).unwrap()
+    }
+
+    #[test]
+    fn test_from_path() {
+        let file = taxonomy_file(&[
+            "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1",
+            "G2\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s2",
+        ]);
+        assert_eq!(file.0.len(), 2);
+        assert_eq!(file.get(&GenomeId("G2".to_string())).unwrap().species.0, "s__s2");
+    }
+
+    #[test]
+    fn test_from_bufreader_invalid() {
+        let content = "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1\nG1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1\n";
+        assert!(TaxonomyFile::from_bufreader(BufReader::new(content.as_bytes())).is_err());
+        assert!(TaxonomyFile::from_bufreader(BufReader::new("G1 d__d1".as_bytes())).is_err());
+    }
+
+    #[test]
+    fn test_to_tree() {
+        let tree = taxonomy_file(&[
+            "G3\td__d1;p__p1;c__c1;o__o1;f__f1;g__g2;s__s3",
+            "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1",
+            "G2\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1",
+            "G4\td__d2;p__p2;c__c2;o__o2;f__f2;g__;s__",
+        ]).to_tree().unwrap();
+
+        let taxon = |x: &str| Taxon(x.to_string());
+        let ids = |x: Vec<&GenomeId>| x.into_iter().map(|x| x.0.clone()).collect::<Vec<String>>();
+        assert_eq!(tree.roots(), &[taxon("d__d1"), taxon("d__d2")]);
+        assert_eq!(tree.children_of(&taxon("f__f1")), &[taxon("g__g1"), taxon("g__g2")]);
+        assert!(tree.children_of(&taxon("s__s1")).is_empty());
+        assert!(tree.children_of(&taxon("g__")).is_empty());
+        assert_eq!(tree.parent_of(&taxon("g__g2")), Some(&taxon("f__f1")));
+        assert_eq!(tree.parent_of(&taxon("d__d1")), None);
+        assert_eq!(ids(tree.leaves_under(&taxon("s__s1"))), vec!["G1", "G2"]);
+        assert_eq!(ids(tree.leaves_under(&taxon("d__d1"))), vec!["G1", "G2", "G3"]);
+        assert_eq!(ids(tree.leaves_under(&taxon("f__f2"))), vec!["G4"]);
+        assert!(tree.leaves_under(&taxon("g__missing")).is_empty());
+    }
+
+    #[test]
+    fn test_to_tree_conflicting_parents() {
+        let file = taxonomy_file(&[
+            "G1\td__d1;p__p1;c__c1;o__o1;f__f1;g__g1;s__s1",
+            "G2\td__d1;p__p1;c__c1;o__o1;f__f2;g__g1;s__s2",
+        ]);
+        assert!(file.to_tree().is_err());
+    }
+}
@@ -22,6 +22,55 @@ pub enum TaxonomyRank {
 }
 
 impl TaxonomyRank {
+    /// Returns an [Iterator] to all [TaxonomyRank]s, from highest (domain) to lowest (species).
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// let prefixes: Vec<String> = TaxonomyRank::all().map(|rank| rank.prefix()).collect();
+    /// assert_eq!(prefixes, vec!["d", "p", "c", "o", "f", "g", "s"]);
+    /// ```
+    pub fn all() -> impl Iterator<Item=TaxonomyRank> {
+        TAXONOMY_RANKS.iter().cloned()
+    }
+
+    /// Return the depth of this [TaxonomyRank], i.e. its index within [TAXONOMY_RANKS]
+    /// (domain is 0, species is 6).
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// assert_eq!(TaxonomyRank::Domain.index(), 0);
+    /// assert_eq!(TaxonomyRank::Species.index(), 6);
+    /// ```
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns true if this [TaxonomyRank] is higher (i.e. closer to the domain) than `other`.
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// assert!(TaxonomyRank::Family.is_above(&TaxonomyRank::Genus));
+    /// assert!(!TaxonomyRank::Genus.is_above(&TaxonomyRank::Genus));
+    /// ```
+    pub fn is_above(&self, other: &TaxonomyRank) -> bool {
+        self.index() < other.index()
+    }
+
+    /// Returns true if this [TaxonomyRank] is lower (i.e. closer to the species) than `other`.
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// assert!(TaxonomyRank::Species.is_below(&TaxonomyRank::Genus));
+    /// assert!(!TaxonomyRank::Genus.is_below(&TaxonomyRank::Genus));
+    /// ```
+    pub fn is_below(&self, other: &TaxonomyRank) -> bool {
+        self.index() > other.index()
+    }
+
     /// Return the prefix associated with this [TaxonomyRank].
     ///
     /// ```
@@ -41,6 +90,27 @@ impl TaxonomyRank {
         }
     }
 
+    /// Return the [TaxonomyRank] associated with a prefix, the inverse of [TaxonomyRank::prefix].
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    ///
+    /// assert_eq!(TaxonomyRank::from_prefix("d"), Some(TaxonomyRank::Domain));
+    /// assert_eq!(TaxonomyRank::from_prefix("x"), None);
+    /// ```
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix {
+            "d" => Some(TaxonomyRank::Domain),
+            "p" => Some(TaxonomyRank::Phylum),
+            "c" => Some(TaxonomyRank::Class),
+            "o" => Some(TaxonomyRank::Order),
+            "f" => Some(TaxonomyRank::Family),
+            "g" => Some(TaxonomyRank::Genus),
+            "s" => Some(TaxonomyRank::Species),
+            _ => None
+        }
+    }
+
     /// Returns the next [TaxonomyRank] lower than this one.
     ///
     /// ```
@@ -142,6 +212,38 @@ mod tests {
         assert_eq!(TaxonomyRank::Species.prefix(), "s");
     }
 
+    #[test]
+    fn test_all_index() {
+        let ranks: Vec<TaxonomyRank> = TaxonomyRank::all().collect();
+        assert_eq!(ranks, TAXONOMY_RANKS.to_vec());
+        for (idx, rank) in TaxonomyRank::all().enumerate() {
+            assert_eq!(rank.index(), idx);
+            assert_eq!(TAXONOMY_RANKS[rank.index()], rank);
+        }
+    }
+
+    #[test]
+    fn test_is_above_below() {
+        for rank in TAXONOMY_RANKS {
+            for other in TAXONOMY_RANKS {
+                assert_eq!(rank.is_above(&other), other.higher_ranks().any(|r| r == rank));
+                assert_eq!(rank.is_below(&other), other.lower_ranks().any(|r| r == rank));
+            }
+        }
+        assert!(TaxonomyRank::Domain.is_above(&TaxonomyRank::Species));
+        assert!(!TaxonomyRank::Domain.is_below(&TaxonomyRank::Species));
+    }
+
+    #[test]
+    fn test_from_prefix() {
+        for rank in TAXONOMY_RANKS {
+            assert_eq!(TaxonomyRank::from_prefix(&rank.prefix()), Some(rank));
+        }
+        assert_eq!(TaxonomyRank::from_prefix("x"), None);
+        assert_eq!(TaxonomyRank::from_prefix(""), None);
+        assert_eq!(TaxonomyRank::from_prefix("D"), None);
+    }
+
     #[test]
     fn test_lower() {
         assert_eq!(TaxonomyRank::Domain.lower(), Some(TaxonomyRank::Phylum));
@@ -0,0 +1,85 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::{ProleError, ProleResult};
+use crate::util::io::FileWriter;
+
+/// A multiple sequence alignment, allowing the same methods to be used across formats
+/// (e.g. [crate::hmm::hmmalign_file::HmmAlignFile] and [crate::hmm::clustal_file::ClustalFile]).
+pub trait Alignment {
+    /// Returns the aligned sequences keyed by ID. This is borrowed where the format stores
+    /// the alignment directly, or computed where it is a view (e.g. the masked columns).
+    fn sequences(&self) -> Cow<'_, HashMap<String, String>>;
+
+    /// Returns the number of columns in the alignment.
+    fn length(&self) -> usize;
+
+    /// Returns the IDs (sorted) and the pairwise percent identity matrix (in the same order).
+    /// Columns gapped (`-` or `.`) in either sequence are ignored, and the comparison is
+    /// case-insensitive. Pairs without any comparable columns have an identity of 0.
+    ///
+    /// This is O(N² × L) for N sequences of length L, so it is intended for modest
+    /// marker alignments rather than whole-genome MSAs.
+    fn identity_matrix(&self) -> (Vec<String>, Vec<Vec<f64>>) {
+        let sequences = self.sequences();
+        let mut ids: Vec<String> = sequences.keys().cloned().collect();
+        ids.sort();
+
+        let residues: Vec<Vec<Option<u8>>> = ids.iter()
+            .map(|id| {
+                sequences[id].bytes()
+                    .map(|c| Some(c.to_ascii_uppercase()).filter(|&c| c != b'-' && c != b'.'))
+                    .collect()
+            })
+            .collect();
+
+        let mut matrix = vec![vec![0.0; ids.len()]; ids.len()];
+        for i in 0..residues.len() {
+            for j in i..residues.len() {
+                let (mut n_same, mut n_cmp) = (0usize, 0usize);
+                for (a, b) in residues[i].iter().zip(residues[j].iter()) {
+                    if let (Some(a), Some(b)) = (a, b) {
+                        n_cmp += 1;
+                        if a == b {
+                            n_same += 1;
+                        }
+                    }
+                }
+                let identity = if n_cmp == 0 { 0.0 } else { 100.0 * n_same as f64 / n_cmp as f64 };
+                matrix[i][j] = identity;
+                matrix[j][i] = identity;
+            }
+        }
+        (ids, matrix)
+    }
+
+    /// Write the alignment (sorted by ID) to [Path] in relaxed PHYLIP format (i.e. names are
+    /// not truncated), the output is gz compressed if the extension is `.gz`.
+    ///
+    /// The first line is `<ntaxa> <nsites>`, followed by one `<name> <alignment>` line per
+    /// sequence. An error is returned if an ID contains whitespace, as it can't be represented,
+    /// or if any sequence is not [Alignment::length] long.
+    fn write_phylip(&self, path: &Path) -> ProleResult<()> {
+        let sequences = self.sequences();
+        let mut ids: Vec<&String> = sequences.keys().collect();
+        ids.sort();
+        for id in &ids {
+            if id.contains(char::is_whitespace) {
+                return Err(ProleError::Exit(format!("PHYLIP names cannot contain whitespace: '{}'", id)));
+            }
+            let seq_len = sequences[*id].chars().count();
+            if seq_len != self.length() {
+                return Err(ProleError::Exit(format!("Alignment has length {}, expected {}: {}", seq_len, self.length(), id)));
+            }
+        }
+
+        let mut writer = FileWriter::create(path)?;
+        writeln!(writer, "{} {}", ids.len(), self.length()).map_err(ProleError::IoError)?;
+        for id in ids {
+            writeln!(writer, "{} {}", id, sequences[id]).map_err(ProleError::IoError)?;
+        }
+        writer.finish()
+    }
+}
@@ -0,0 +1,48 @@
+use crate::genome::fasta_file::FastaFile;
+use crate::hmm::hmmsearch_file::{HmmSearchFile, HmmSearchHit};
+
+/// Pair each sequence ID in `fasta` (in input order) with its best hit in `hits`
+/// (see [HmmSearchFile::best_hit_per_target]), or [None] if the sequence has no hits.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::genome::fasta_file::FastaFile;
+/// use prole::hmm::annotate::join_hits;
+/// use prole::hmm::hmmsearch_file::HmmSearchFile;
+///
+/// let fasta = FastaFile::from_path(Path::new("/path/to/genes.faa")).unwrap();
+/// let hits = HmmSearchFile::from_path(Path::new("/path/to/tblout.tsv")).unwrap();
+/// for (gene_id, hit) in join_hits(&fasta, &hits) {
+///     println!("{}\t{}", gene_id, hit.map_or("", |hit| hit.query_name.as_str()));
+/// }
+/// ```
+pub fn join_hits<'a>(fasta: &'a FastaFile, hits: &'a HmmSearchFile) -> impl Iterator<Item=(&'a str, Option<&'a HmmSearchHit>)> {
+    let best = hits.best_hit_per_target();
+    fasta.ids().map(move |id| (id, best.get(id).copied()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+
+    #[test]
+    fn test_join_hits() {
+        let mut map = IndexMap::new();
+        map.insert("gene_2".to_string(), (None, "MKP".to_string()));
+        map.insert("gene_1".to_string(), (None, "MST".to_string()));
+        let fasta = FastaFile::from_iter(map);
+        let hits = HmmSearchFile(vec![
+            HmmSearchHit::from_string("gene_1 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_1 -          TIGR00002            TIGR00002    1.9e-30   99.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_3 -          TIGR00001            TIGR00001    1.9e-26   50.0   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+        ]);
+
+        let joined: Vec<(&str, Option<&str>)> = join_hits(&fasta, &hits)
+            .map(|(id, hit)| (id, hit.map(|hit| hit.query_name.as_str())))
+            .collect();
+        assert_eq!(joined, vec![("gene_2", None), ("gene_1", Some("TIGR00002"))]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::error::{ProleError, ProleResult};
+use crate::hmm::alignment::Alignment;
+use crate::util::parse::read_lines;
+
+/// Wraps a CLUSTAL format alignment file, mapping each sequence ID to its aligned sequence.
+///
+/// ## Example
+/// The alignment is split into interleaved blocks, each of which may be followed by a
+/// conservation line. Some tools append the cumulative residue count to each line.
+///
+/// ```text
+/// CLUSTAL W (1.83) multiple sequence alignment
+///
+/// G1      MKV-LA 5
+/// G2      MKVILA 6
+///         *** **
+///
+/// G1      TT 7
+/// G2      T- 7
+///         *
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClustalFile(pub HashMap<String, String>);
+
+impl ClustalFile {
+    /// Read the content from a [BufReader] and parse it into a [ClustalFile].
+    ///
+    /// An error is returned if the `CLUSTAL` header is missing, or the sequences are not all
+    /// the same length.
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::hmm::clustal_file::ClustalFile;
+    ///
+    /// let content = "CLUSTAL W (1.83) multiple sequence alignment\n\nG1  MKV-LA 5\nG2  MKVILA 6\n    *** **\n";
+    /// let alignment = ClustalFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+    /// assert_eq!(alignment.0["G1"], "MKV-LA");
+    /// ```
+    pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        let mut out: HashMap<String, String> = HashMap::new();
+        let mut seen_header = false;
+
+        for line in read_lines(buf) {
+            let line = line?;
+            if !seen_header {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if !line.starts_with("CLUSTAL") {
+                    return Err(ProleError::Exit(format!("Expected a CLUSTAL header: {}", line)));
+                }
+                seen_header = true;
+                continue;
+            }
+
+            // Conservation lines are indented, and blocks are separated by empty lines
+            if line.trim().is_empty() || line.starts_with(char::is_whitespace) {
+                continue;
+            }
+
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let (gene_id, seq) = match cols.as_slice() {
+                [gene_id, seq] => (gene_id, seq),
+                [gene_id, seq, count] if count.chars().all(|c| c.is_ascii_digit()) => (gene_id, seq),
+                _ => return Err(ProleError::Exit(format!("Error parsing: {}", line))),
+            };
+            out.entry(gene_id.to_string()).or_default().push_str(seq);
+        }
+
+        if !seen_header {
+            return Err(ProleError::Exit("Missing CLUSTAL header".to_string()));
+        }
+        let mut lengths = out.iter().map(|(gene_id, seq)| (gene_id, seq.chars().count()));
+        if let Some((_, expected)) = lengths.next() {
+            if let Some((gene_id, length)) = lengths.find(|(_, length)| *length != expected) {
+                return Err(ProleError::Exit(format!("Alignment has length {}, expected {}: {}", length, expected, gene_id)));
+            }
+        }
+        Ok(Self(out))
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [ClustalFile], the reader
+    /// is buffered internally.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
+    }
+
+    /// Read the content from a [Path] and parse it into a [ClustalFile].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::clustal_file::ClustalFile;
+    ///
+    /// let alignment = ClustalFile::from_path(Path::new("/path/to/file.aln")).unwrap();
+    /// ```
+    pub fn from_path(path: &Path) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        Self::from_bufreader(BufReader::new(file))
+    }
+}
+
+
+impl Alignment for ClustalFile {
+    fn sequences(&self) -> Cow<'_, HashMap<String, String>> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn length(&self) -> usize {
+        self.0.values().next().map_or(0, |seq| seq.chars().count())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "CLUSTAL W (1.83) multiple sequence alignment").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "G1      MKV-LA 5").unwrap();
+        writeln!(file, "G2      MKVILA 6").unwrap();
+        writeln!(file, "G3      MKVILA").unwrap();
+        writeln!(file, "        *** **").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "G1      TT 7").unwrap();
+        writeln!(file, "G2      T- 7").unwrap();
+        writeln!(file, "G3      TA").unwrap();
+        writeln!(file, "          ").unwrap();
+
+        let result = ClustalFile::from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.0.len(), 3);
+        assert_eq!(result.0["G1"], "MKV-LATT");
+        assert_eq!(result.0["G2"], "MKVILAT-");
+        assert_eq!(result.0["G3"], "MKVILATA");
+    }
+
+    #[test]
+    fn test_from_reader_invalid() {
+        assert!(ClustalFile::from_reader("G1  MKV\n".as_bytes()).is_err());
+        assert!(ClustalFile::from_reader("".as_bytes()).is_err());
+        assert!(ClustalFile::from_reader("CLUSTAL\n\nG1  MKV\nG2  MK\n".as_bytes()).is_err());
+        assert!(ClustalFile::from_reader("CLUSTAL\n\nG1  MKV 3 x\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_alignment() {
+        let alignment = ClustalFile::from_reader("CLUSTAL\n\nG2  MKV-LA\nG1  MKVILA\n".as_bytes()).unwrap();
+        assert_eq!(alignment.length(), 6);
+        assert_eq!(alignment.sequences()["G1"], "MKVILA");
+
+        let (gene_ids, matrix) = alignment.identity_matrix();
+        assert_eq!(gene_ids, vec!["G1", "G2"]);
+        assert_eq!(matrix, vec![vec![100.0, 100.0], vec![100.0, 100.0]]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.phy");
+        alignment.write_phylip(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2 6\nG1 MKVILA\nG2 MKV-LA\n");
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::{ProleError, ProleResult};
+use crate::hmm::hmmalign_file::HmmAlignFile;
+use crate::util::io::is_gz;
+
+/// Returns an error if any genome is listed more than once.
+fn check_unique(genomes: &[String]) -> ProleResult<()> {
+    let mut seen = HashSet::with_capacity(genomes.len());
+    for genome in genomes {
+        if !seen.insert(genome) {
+            return Err(ProleError::Exit(format!("Duplicate genome: {}", genome)));
+        }
+    }
+    Ok(())
+}
+
+/// Load the [HmmAlignFile] for a marker, returning its masked alignments keyed by
+/// genome and the number of masked columns.
+fn load_marker(marker: &str, path: &Path) -> ProleResult<(HashMap<String, String>, usize)> {
+    let alignment = HmmAlignFile::from_path(path)?;
+    let width = alignment.mask_idx.len();
+    let mut out = HashMap::with_capacity(alignment.seq.len());
+    for (genome, seq) in alignment.masked_alignments()? {
+        if seq.len() != width {
+            return Err(ProleError::Exit(format!("Non-ASCII alignment for {} in marker: {}", genome, marker)));
+        }
+        out.insert(genome.clone(), seq);
+    }
+    Ok((out, width))
+}
+
+/// Concatenate the masked alignment of each marker (in the order given) for each genome, where
+/// the sequence IDs of each [HmmAlignFile] are the genome IDs. A genome missing from a marker
+/// is padded with gaps (`-`), and any genome not in `genomes` is ignored.
+///
+/// All markers are held in memory, see [write_concatenated] for large alignments.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::hmm::concat::concatenate;
+///
+/// let markers = [("PF00001", Path::new("/path/to/PF00001.sto")), ("PF00002", Path::new("/path/to/PF00002.sto"))];
+/// let genomes = vec!["G1".to_string(), "G2".to_string()];
+/// let msa = concatenate(&markers, &genomes).unwrap();
+/// ```
+pub fn concatenate(markers: &[(&str, &Path)], genomes: &[String]) -> ProleResult<HashMap<String, String>> {
+    check_unique(genomes)?;
+    let mut out: HashMap<String, String> = genomes.iter().map(|genome| (genome.clone(), String::new())).collect();
+    for &(marker, path) in markers {
+        let (alignments, width) = load_marker(marker, path)?;
+        for (genome, seq) in out.iter_mut() {
+            match alignments.get(genome) {
+                Some(aln) => seq.push_str(aln),
+                None => seq.push_str(&"-".repeat(width)),
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Write the concatenated alignment (see [concatenate]) as a FASTA file to [Path], with the
+/// records in the same order as `genomes`. The output is identical to writing the result of
+/// [concatenate] with each sequence on a single line.
+///
+/// Only a single marker is held in memory at a time. Each marker file is read twice: once to
+/// determine the alignment width (so that a gap-filled output can be allocated), then again to
+/// write each genome's alignment in place. As the output is written out of order, it cannot be
+/// gz compressed.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::hmm::concat::write_concatenated;
+///
+/// let markers = [("PF00001", Path::new("/path/to/PF00001.sto")), ("PF00002", Path::new("/path/to/PF00002.sto"))];
+/// let genomes = vec!["G1".to_string(), "G2".to_string()];
+/// write_concatenated(&markers, &genomes, Path::new("/path/to/msa.faa")).unwrap();
+/// ```
+pub fn write_concatenated(markers: &[(&str, &Path)], genomes: &[String], out: &Path) -> ProleResult<()> {
+    if is_gz(out) {
+        return Err(ProleError::Exit(format!("Unable to write a concatenated alignment to a gz file: {}", out.display())));
+    }
+    check_unique(genomes)?;
+
+    // Determine the offset of each marker within the concatenated alignment
+    let mut marker_offsets = Vec::with_capacity(markers.len());
+    let mut total_width = 0;
+    for &(marker, path) in markers {
+        marker_offsets.push(total_width);
+        total_width += load_marker(marker, path)?.1;
+    }
+
+    // Write a gap-only record for each genome, recording where each sequence starts
+    let file = File::create(out).map_err(ProleError::IoError)?;
+    let mut writer = BufWriter::new(file);
+    let mut seq_offsets: HashMap<&str, u64> = HashMap::with_capacity(genomes.len());
+    let mut offset = 0;
+    let gaps = vec![b'-'; total_width];
+    for genome in genomes {
+        let header = format!(">{}\n", genome);
+        writer.write_all(header.as_bytes()).map_err(ProleError::IoError)?;
+        writer.write_all(&gaps).map_err(ProleError::IoError)?;
+        writer.write_all(b"\n").map_err(ProleError::IoError)?;
+        seq_offsets.insert(genome, (offset + header.len()) as u64);
+        offset += header.len() + total_width + 1;
+    }
+
+    // Fill in each marker, one at a time
+    for (&(marker, path), &marker_offset) in markers.iter().zip(marker_offsets.iter()) {
+        let (alignments, width) = load_marker(marker, path)?;
+        if marker_offset + width > total_width {
+            return Err(ProleError::Exit(format!("Marker changed while concatenating: {}", marker)));
+        }
+        for genome in genomes {
+            if let Some(seq) = alignments.get(genome) {
+                writer.seek(SeekFrom::Start(seq_offsets[genome.as_str()] + marker_offset as u64)).map_err(ProleError::IoError)?;
+                writer.write_all(seq.as_bytes()).map_err(ProleError::IoError)?;
+            }
+        }
+    }
+    writer.flush().map_err(ProleError::IoError)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn write_marker(dir: &Path, name: &str, rows: &[(&str, &str)], rf: &str) -> PathBuf {
+        let path = dir.join(name);
+        let mut content = "# STOCKHOLM 1.0\n".to_string();
+        for (genome, seq) in rows {
+            content.push_str(&format!("{} {}\n#=GR {} PP {}\n", genome, seq, genome, "9".repeat(seq.len())));
+        }
+        content.push_str(&format!("#=GC PP_cons {}\n#=GC RF {}\n//\n", "9".repeat(rf.len()), rf));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_write_concatenated() {
+        let dir = tempfile::tempdir().unwrap();
+        let m1 = write_marker(dir.path(), "m1.sto", &[("G1", "AkCD"), ("G2", "A.-D"), ("G4", "WWWW")], "x.xx");
+        let m2 = write_marker(dir.path(), "m2.sto", &[("G3", "EF"), ("G1", "E-")], "xx");
+        let markers = [("m1", m1.as_path()), ("m2", m2.as_path())];
+        let genomes = vec!["G1".to_string(), "G2".to_string(), "G3".to_string()];
+
+        let msa = concatenate(&markers, &genomes).unwrap();
+        assert_eq!(msa.len(), 3);
+        assert_eq!(msa["G1"], "ACDE-");
+        assert_eq!(msa["G2"], "A-D--");
+        assert_eq!(msa["G3"], "---EF");
+
+        let out = dir.path().join("msa.faa");
+        write_concatenated(&markers, &genomes, &out).unwrap();
+        let expected: String = genomes.iter().map(|genome| format!(">{}\n{}\n", genome, msa[genome])).collect();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), expected);
+
+        let duplicates = vec!["G1".to_string(), "G1".to_string()];
+        assert!(write_concatenated(&markers, &duplicates, &out).is_err());
+        assert!(write_concatenated(&markers, &genomes, &dir.path().join("msa.faa.gz")).is_err());
+    }
+}
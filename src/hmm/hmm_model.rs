@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::error::{ProleError, ProleResult};
+use crate::util::io::open_reader;
+use crate::util::parse::read_lines;
+
+/// Per-sequence bit score cutoffs keyed by the model `NAME` (i.e. the `query_name` reported by hmmsearch).
+pub type ModelCutoffs = HashMap<String, f64>;
+
+/// A pair of bit score cutoffs (`GA`, `TC`, or `NC`) as stored in a HMMER3 model.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HmmCutoff {
+    /// The per-sequence bit score cutoff.
+    pub seq: f64,
+    /// The per-domain bit score cutoff.
+    pub dom: f64,
+}
+
+impl HmmCutoff {
+    /// Parse the values following a cutoff tag, e.g. `25.00 25.00;`.
+    fn from_string(string: &str) -> ProleResult<Self> {
+        let values = string.trim().trim_end_matches(';').split_whitespace().collect::<Vec<&str>>();
+        if values.len() != 2 {
+            return Err(ProleError::Exit(format!("Error parsing cutoff: {}", string)));
+        }
+        Ok(Self {
+            seq: values[0].parse().map_err(ProleError::ParseFloatError)?,
+            dom: values[1].parse().map_err(ProleError::ParseFloatError)?,
+        })
+    }
+}
+
+/// The header of a single model within a HMMER3 `.hmm` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HmmHeader {
+    /// The model name (`NAME`), reported as the `query_name` by hmmsearch.
+    pub name: String,
+    /// The model accession (`ACC`).
+    pub accession: Option<String>,
+    /// The number of match states in the model (`LENG`).
+    pub length: u32,
+    /// The gathering threshold (`GA`).
+    pub ga: Option<HmmCutoff>,
+    /// The trusted cutoff (`TC`).
+    pub tc: Option<HmmCutoff>,
+    /// The noise cutoff (`NC`).
+    pub nc: Option<HmmCutoff>,
+}
+
+/// Methods for reading a HMMER3 `.hmm` file, which may contain multiple concatenated models.
+///
+/// Only the header of each model is parsed, the emission matrices are skipped.
+pub struct HmmModel;
+
+impl HmmModel {
+    /// Read the model headers from a [BufReader].
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use prole::hmm::hmm_model::HmmModel;
+    ///
+    /// let reader = BufReader::new(File::open("/path/to/file.hmm").unwrap());
+    /// let headers = HmmModel::headers_from_bufreader(reader).unwrap();
+    /// ```
+    pub fn headers_from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Vec<HmmHeader>> {
+        let mut out = vec![];
+        let mut in_header = false;
+        let mut name: Option<String> = None;
+        let mut accession: Option<String> = None;
+        let mut length: Option<u32> = None;
+        let mut ga: Option<HmmCutoff> = None;
+        let mut tc: Option<HmmCutoff> = None;
+        let mut nc: Option<HmmCutoff> = None;
+
+        for line in read_lines(buf) {
+            let line = line?;
+            if line.starts_with("HMMER3") {
+                in_header = true;
+                continue;
+            } else if line.starts_with("//") {
+                let name = name.take()
+                    .ok_or_else(|| ProleError::Exit("Model is missing NAME".to_string()))?;
+                let length = length.take()
+                    .ok_or_else(|| ProleError::Exit(format!("Model is missing LENG: {}", name)))?;
+                out.push(HmmHeader {
+                    name,
+                    accession: accession.take(),
+                    length,
+                    ga: ga.take(),
+                    tc: tc.take(),
+                    nc: nc.take(),
+                });
+                in_header = false;
+                continue;
+            } else if !in_header {
+                continue;
+            }
+
+            let (tag, value) = line.split_once(char::is_whitespace).unwrap_or((line.as_str(), ""));
+            match tag {
+                "NAME" => name = Some(value.trim().to_string()),
+                "ACC" => accession = Some(value.trim().to_string()),
+                "LENG" => length = Some(value.trim().parse().map_err(ProleError::ParseIntError)?),
+                "GA" => ga = Some(HmmCutoff::from_string(value)?),
+                "TC" => tc = Some(HmmCutoff::from_string(value)?),
+                "NC" => nc = Some(HmmCutoff::from_string(value)?),
+                // The start of the emission matrix marks the end of the header
+                "HMM" => in_header = false,
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
+
+    /// Read the model headers from a [Path].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmm_model::HmmModel;
+    ///
+    /// let headers = HmmModel::headers_from_path(Path::new("/path/to/file.hmm")).unwrap();
+    /// ```
+    pub fn headers_from_path(path: &Path) -> ProleResult<Vec<HmmHeader>> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(file);
+        Self::headers_from_bufreader(reader)
+    }
+
+    /// Read the model headers from a gz compressed file at [Path].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmm_model::HmmModel;
+    ///
+    /// let headers = HmmModel::headers_from_path_gz(Path::new("/path/to/file.hmm.gz")).unwrap();
+    /// ```
+    pub fn headers_from_path_gz(path: &Path) -> ProleResult<Vec<HmmHeader>> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let gz = GzDecoder::new(file);
+        let reader = BufReader::new(gz);
+        Self::headers_from_bufreader(reader)
+    }
+}
+
+/// Read the gathering (`GA`) cutoffs from a (possibly concatenated) HMMER3 `.hmm` file.
+///
+/// The returned map is keyed by the model `NAME` (i.e. the `query_name` reported by hmmsearch),
+/// the value is the per-sequence gathering threshold (the first of the two `GA` values).
+/// Models without a `GA` line are omitted. The file may be compressed (see [open_reader]).
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::hmm::hmm_model::parse_ga_cutoffs;
+///
+/// let cutoffs = parse_ga_cutoffs(Path::new("/path/to/tigrfam.hmm")).unwrap();
+/// ```
+pub fn parse_ga_cutoffs(path: &Path) -> ProleResult<ModelCutoffs> {
+    let headers = HmmModel::headers_from_bufreader(open_reader(path)?)?;
+    Ok(headers.into_iter()
+        .filter_map(|header| header.ga.map(|ga| (header.name, ga.seq)))
+        .collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const HMM_FILE: &str = "HMMER3/f [3.1b2 | February 2015]
+NAME  TIGR00001
+ACC   TIGR00001
+DESC  ribosomal protein bL35
+LENG  64
+ALPH  amino
+GA    25.00 25.00;
+TC    25.10 25.10;
+NC    24.90 24.90;
+HMM          A        C        D        E        F        G        H        I        K        L        M        N        P        Q        R        S        T        V        W        Y
+            m->m     m->i     m->d     i->m     i->i     d->m     d->d
+  COMPO   2.09140  4.37807  3.36115  2.73245  3.60722  2.70112  3.56138  3.05063  2.24624  2.66376  3.81278  3.13036  3.38811  3.10513  2.55722  2.65578  2.98129  2.74059  4.66262  3.75838
+//
+HMMER3/f [3.1b2 | February 2015]
+NAME  TIGR00002
+LENG  128
+GA    112.35 80.10;
+HMM          A        C        D        E        F        G        H        I        K        L        M        N        P        Q        R        S        T        V        W        Y
+//
+HMMER3/f [3.1b2 | February 2015]
+NAME  TIGR00003
+LENG  32
+//
+";
+
+    #[test]
+    fn test_headers_from_path() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", HMM_FILE).unwrap();
+
+        let result = HmmModel::headers_from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], HmmHeader {
+            name: "TIGR00001".to_string(),
+            accession: Some("TIGR00001".to_string()),
+            length: 64,
+            ga: Some(HmmCutoff { seq: 25.0, dom: 25.0 }),
+            tc: Some(HmmCutoff { seq: 25.1, dom: 25.1 }),
+            nc: Some(HmmCutoff { seq: 24.9, dom: 24.9 }),
+        });
+        assert_eq!(result[1].name, "TIGR00002");
+        assert_eq!(result[1].accession, None);
+        assert_eq!(result[1].length, 128);
+        assert_eq!(result[1].ga, Some(HmmCutoff { seq: 112.35, dom: 80.10 }));
+        assert_eq!(result[1].tc, None);
+        assert_eq!(result[2].name, "TIGR00003");
+        assert_eq!(result[2].ga, None);
+    }
+
+    #[test]
+    fn test_headers_from_path_gz() {
+        let mut file = NamedTempFile::new().unwrap();
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(HMM_FILE.as_bytes()).unwrap();
+        let compressed_bytes = e.finish().unwrap();
+        file.write_all(&compressed_bytes).unwrap();
+
+        let result = HmmModel::headers_from_path_gz(&file.into_temp_path()).unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[2].length, 32);
+    }
+
+    #[test]
+    fn test_headers_missing_length() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "HMMER3/f [3.1b2 | February 2015]").unwrap();
+        writeln!(file, "NAME  TIGR00001").unwrap();
+        writeln!(file, "//").unwrap();
+
+        let result = HmmModel::headers_from_path(&file.into_temp_path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_ga_cutoffs() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", HMM_FILE).unwrap();
+
+        let result = parse_ga_cutoffs(&file.into_temp_path()).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.get("TIGR00001"), Some(&25.0));
+        assert_eq!(result.get("TIGR00002"), Some(&112.35));
+        assert_eq!(result.get("TIGR00003"), None);
+
+        let mut file = NamedTempFile::new().unwrap();
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(HMM_FILE.as_bytes()).unwrap();
+        file.write_all(&e.finish().unwrap()).unwrap();
+        assert_eq!(parse_ga_cutoffs(&file.into_temp_path()).unwrap(), result);
+    }
+}
@@ -1,27 +1,50 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufReader, Write};
 use std::path::Path;
 
 use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::error::{ProleError, ProleResult};
+use crate::hmm::alignment::Alignment;
+use crate::util::io::FileWriter;
+use crate::util::parse::read_lines;
 
 lazy_static! {
-    static ref RE_GR: Regex = Regex::new(r"^#=GR ([^\s]+)\s+PP\s+([^\s]+)$").unwrap();
-    static ref RE_PP_CONS: Regex = Regex::new(r"^#=GC PP_cons\s+([^\s]+)$").unwrap();
-    static ref RE_GC_RF: Regex = Regex::new(r"^#=GC RF\s+([^\s]+)$").unwrap();
-    static ref RE_ALIGN: Regex = Regex::new(r"^([^\s]+)\s+([^\s]+)$").unwrap();
+    static ref RE_GR: Regex = Regex::new(r"^#=GR ([^\s]+)\s+PP\s+([^\s].*)$").unwrap();
+    static ref RE_PP_CONS: Regex = Regex::new(r"^#=GC PP_cons\s+([^\s].*)$").unwrap();
+    static ref RE_GC_RF: Regex = Regex::new(r"^#=GC RF\s+([^\s].*)$").unwrap();
+    static ref RE_ALIGN: Regex = Regex::new(r"^([^\s]+)\s+([^\s].*)$").unwrap();
+}
+
+/// Remove all whitespace from an alignment row, as some Stockholm writers separate the
+/// columns into blocks (e.g. `AAAAA AAAAA`).
+fn strip_whitespace(string: &str) -> String {
+    string.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Returns true if the `#=GC RF` character denotes a match column. HMMER uses `x`, whereas some
+/// Stockholm writers use the consensus residue (uppercase). Anything else (e.g. `.`) is an insert.
+fn is_match_column(c: char) -> bool {
+    c == 'x' || c.is_ascii_uppercase()
 }
 
 /// Wraps the output of a HMMER alignment file.
+///
+/// Each sequence is expected on a single line, any whitespace within the aligned portion of a
+/// line (e.g. column block separators) is removed.
+#[derive(Debug, Clone)]
 pub struct HmmAlignFile {
     pub seq: HashMap<String, String>,
     pub pp: HashMap<String, String>,
     pub pp_cons: String,
     pub mask: Vec<bool>,
     pub mask_idx: Vec<usize>,
+    /// The match columns of the `#=GC RF` line as read, i.e. unchanged by the methods that
+    /// remove columns from `mask`.
+    pub rf_mask: Vec<bool>,
 }
 
 impl HmmAlignFile {
@@ -42,8 +65,8 @@ impl HmmAlignFile {
         let mut mask = vec![];
         let mut mask_idx = vec![];
 
-        for line in buf.lines() {
-            let line = line.map_err(ProleError::IoError)?;
+        for line in read_lines(buf) {
+            let line = line?;
             if line.is_empty() || line.starts_with("# STOCKHOLM") || line.starts_with("//") {
                 continue;
             } else if line.starts_with("#=GR ") {
@@ -54,7 +77,7 @@ impl HmmAlignFile {
                 if pp.contains_key(&gene_id) {
                     return Err(ProleError::Exit(format!("Duplicate: {}", line)));
                 }
-                pp.insert(gene_id, hits[2].to_string());
+                pp.insert(gene_id, strip_whitespace(&hits[2]));
             } else if line.starts_with("#=GC PP_cons") {
                 // Within conserved
                 let hits = RE_PP_CONS.captures(&line)
@@ -62,7 +85,7 @@ impl HmmAlignFile {
                 if !pp_cons.is_empty() {
                     return Err(ProleError::Exit(format!("Duplicate: {}", line)));
                 }
-                pp_cons = hits[1].to_string();
+                pp_cons = strip_whitespace(&hits[1]);
             } else if line.starts_with("#=GC RF") {
                 // Within mask
                 let hits = RE_GC_RF.captures(&line)
@@ -70,8 +93,8 @@ impl HmmAlignFile {
                 if !mask.is_empty() {
                     return Err(ProleError::Exit(format!("Duplicate: {}", line)));
                 }
-                for (idx, char) in hits[1].chars().enumerate() {
-                    if char == 'x' {
+                for (idx, char) in strip_whitespace(&hits[1]).chars().enumerate() {
+                    if is_match_column(char) {
                         mask.push(true);
                         mask_idx.push(idx);
                     } else {
@@ -86,7 +109,7 @@ impl HmmAlignFile {
                 if seq.contains_key(&gene_id) {
                     return Err(ProleError::Exit(format!("Duplicate: {}", line)));
                 }
-                seq.insert(gene_id, hits[2].to_string());
+                seq.insert(gene_id, strip_whitespace(&hits[2]));
             }
         }
 
@@ -108,7 +131,22 @@ impl HmmAlignFile {
         }
 
         // All ok
-        Ok(Self { seq, pp, pp_cons, mask, mask_idx })
+        let rf_mask = mask.clone();
+        Ok(Self { seq, pp, pp_cons, mask, mask_idx, rf_mask })
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [HmmAlignFile], the reader
+    /// is buffered internally.
+    ///
+    /// ```
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let content = "# STOCKHOLM 1.0\nG1 .mAK\n#=GR G1 PP .*79\n#=GC PP_cons ..79\n#=GC RF ..x.\n//\n";
+    /// let alignment = HmmAlignFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(alignment.get_alignment("G1").unwrap(), "A");
+    /// ```
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
     }
 
     /// Read the content from a [Path] and parse it into a [HmmAlignFile].
@@ -144,6 +182,210 @@ impl HmmAlignFile {
         }
         Ok(out)
     }
+
+    /// Returns the indices of the insert state columns, i.e. those not marked as a match column
+    /// by the `#=GC RF` line (see `rf_mask`). Columns removed from the mask after reading
+    /// (e.g. by [HmmAlignFile::remove_gap_only_columns]) are not insert state columns.
+    ///
+    /// ```
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let content = "# STOCKHOLM 1.0\nG1 .mAkI\n#=GR G1 PP .*799\n#=GC PP_cons ..799\n#=GC RF ..x.x\n//\n";
+    /// let alignment = HmmAlignFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(alignment.insertion_columns(), vec![0, 1, 3]);
+    /// ```
+    pub fn insertion_columns(&self) -> Vec<usize> {
+        self.rf_mask.iter().enumerate()
+            .filter(|(_, &is_match)| !is_match)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Remove the masked columns where every sequence has a gap (`-` or `.`), returning the
+    /// number of columns removed. Both `mask` and `mask_idx` are updated, the unmasked
+    /// sequences are unchanged.
+    ///
+    /// ```
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let content = "# STOCKHOLM 1.0\nG1 .mA-\n#=GR G1 PP .*7.\n#=GC PP_cons ..7.\n#=GC RF ..xx\n//\n";
+    /// let mut alignment = HmmAlignFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(alignment.remove_gap_only_columns(), 1);
+    /// assert_eq!(alignment.mask_idx, vec![2]);
+    /// ```
+    pub fn remove_gap_only_columns(&mut self) -> usize {
+        let seqs: Vec<&[u8]> = self.seq.values().map(|seq| seq.as_bytes()).collect();
+        let mask = &mut self.mask;
+        let n_before = self.mask_idx.len();
+        self.mask_idx.retain(|&idx| {
+            let is_gap_only = seqs.iter().all(|seq| seq.get(idx).map_or(true, |&c| c == b'-' || c == b'.'));
+            if is_gap_only {
+                mask[idx] = false;
+            }
+            !is_gap_only
+        });
+        n_before - self.mask_idx.len()
+    }
+
+    /// Returns the occupancy of each masked column, i.e. the fraction of sequences that do not
+    /// have a gap (`-` or `.`) in that column. A masked column beyond the end of a sequence is
+    /// treated as a gap.
+    pub fn column_occupancy(&self) -> Vec<f64> {
+        if self.seq.is_empty() {
+            return vec![0.0; self.mask_idx.len()];
+        }
+        let mut counts = vec![0usize; self.mask_idx.len()];
+        for seq in self.seq.values() {
+            let seq = seq.as_bytes();
+            for (count, &idx) in counts.iter_mut().zip(self.mask_idx.iter()) {
+                if seq.get(idx).map_or(false, |&c| c != b'-' && c != b'.') {
+                    *count += 1;
+                }
+            }
+        }
+        counts.into_iter().map(|count| count as f64 / self.seq.len() as f64).collect()
+    }
+
+    /// Returns the conservation of each masked column, i.e. the frequency of the most common
+    /// non-gap residue (case-insensitive) across all sequences. A column where every sequence
+    /// has a gap (`-` or `.`) has a conservation of 0.
+    ///
+    /// ```
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let content = "# STOCKHOLM 1.0\nG1 AKI\n#=GR G1 PP 799\nG2 A-V\n#=GR G2 PP 7.9\n#=GC PP_cons 799\n#=GC RF xxx\n//\n";
+    /// let alignment = HmmAlignFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(alignment.column_conservation(), vec![1.0, 0.5, 0.5]);
+    /// ```
+    pub fn column_conservation(&self) -> Vec<f64> {
+        let seqs: Vec<&[u8]> = self.seq.values().map(|seq| seq.as_bytes()).collect();
+        self.mask_idx.iter()
+            .map(|&idx| {
+                let mut counts: HashMap<u8, usize> = HashMap::new();
+                for seq in &seqs {
+                    match seq.get(idx) {
+                        Some(b'-') | Some(b'.') | None => {}
+                        Some(c) => *counts.entry(c.to_ascii_uppercase()).or_default() += 1,
+                    }
+                }
+                counts.values().max().map_or(0.0, |&count| count as f64 / seqs.len() as f64)
+            })
+            .collect()
+    }
+
+    /// Returns the masked alignment of each gene, with any masked column where the fraction of
+    /// gaps exceeds `max_gap` (see [HmmAlignFile::column_occupancy]) removed. The order of the
+    /// retained columns is preserved.
+    ///
+    /// ```
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let content = "# STOCKHOLM 1.0\nG1 AKI\n#=GR G1 PP 799\nG2 A-I\n#=GR G2 PP 7.9\n#=GC PP_cons 799\n#=GC RF xxx\n//\n";
+    /// let alignment = HmmAlignFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(alignment.trim_by_gap_fraction(0.4)["G1"], "AI");
+    /// ```
+    pub fn trim_by_gap_fraction(&self, max_gap: f64) -> HashMap<String, String> {
+        let keep: Vec<usize> = self.column_occupancy().into_iter()
+            .zip(self.mask_idx.iter())
+            .filter(|(occupancy, _)| 1.0 - occupancy <= max_gap)
+            .map(|(_, &idx)| idx)
+            .collect();
+        self.seq.iter()
+            .map(|(gene_id, seq)| {
+                let seq_chars: Vec<char> = seq.chars().collect();
+                (gene_id.clone(), keep.iter().map(|&idx| seq_chars.get(idx).copied().unwrap_or('-')).collect())
+            })
+            .collect()
+    }
+
+    /// Write the masked alignment of each gene (sorted by ID) as a FASTA file to [Path],
+    /// the output is gz compressed if the extension is `.gz`. An error is returned if any aligned
+    /// sequence is not the same length as the mask, as the masked alignments would differ in length.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let alignment = HmmAlignFile::from_path(&Path::new("/path/to/file")).unwrap();
+    /// alignment.to_masked_fasta(&Path::new("/path/to/masked.faa")).unwrap();
+    /// ```
+    pub fn to_masked_fasta(&self, path: &Path) -> ProleResult<()> {
+        let records = self.masked_alignments()?;
+        let mut writer = FileWriter::create(path)?;
+        for (gene_id, alignment) in records {
+            writeln!(writer, ">{}\n{}", gene_id, alignment).map_err(ProleError::IoError)?;
+        }
+        writer.finish()
+    }
+
+    /// Write the masked alignment of each gene (sorted by ID) to [Path] in relaxed PHYLIP format
+    /// (i.e. names are not truncated), the output is gz compressed if the extension is `.gz`.
+    ///
+    /// The first line is `<ntaxa> <nsites>`, followed by one `<name> <alignment>` line per gene.
+    /// An error is returned if a gene ID contains whitespace, as it can't be represented, or if
+    /// any aligned sequence is not the same length as the mask.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let alignment = HmmAlignFile::from_path(&Path::new("/path/to/file")).unwrap();
+    /// alignment.write_phylip(&Path::new("/path/to/masked.phy")).unwrap();
+    /// ```
+    pub fn write_phylip(&self, path: &Path) -> ProleResult<()> {
+        self.masked_alignments()?;
+        Alignment::write_phylip(self, path)
+    }
+
+    /// Returns the masked alignment of each gene (sorted by ID), an error is returned if any
+    /// aligned sequence is not the same length as the mask.
+    pub(crate) fn masked_alignments(&self) -> ProleResult<Vec<(&String, String)>> {
+        let mut gene_ids: Vec<&String> = self.seq.keys().collect();
+        gene_ids.sort();
+
+        let mut records = Vec::with_capacity(gene_ids.len());
+        for gene_id in gene_ids {
+            let seq_len = self.seq[gene_id].chars().count();
+            if seq_len != self.mask.len() {
+                return Err(ProleError::Exit(format!("Alignment has length {}, expected {}: {}", seq_len, self.mask.len(), gene_id)));
+            }
+            records.push((gene_id, self.get_alignment(gene_id)?));
+        }
+        Ok(records)
+    }
+
+    /// Returns the gene IDs (sorted) and the pairwise percent identity matrix (in the same order)
+    /// computed over the masked columns, see [Alignment::identity_matrix].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmmalign_file::HmmAlignFile;
+    ///
+    /// let alignment = HmmAlignFile::from_path(&Path::new("/path/to/file")).unwrap();
+    /// let (gene_ids, matrix) = alignment.identity_matrix();
+    /// ```
+    pub fn identity_matrix(&self) -> (Vec<String>, Vec<Vec<f64>>) {
+        Alignment::identity_matrix(self)
+    }
+}
+
+
+impl Alignment for HmmAlignFile {
+    /// Returns the masked alignment of each gene, any masked column beyond the end of a
+    /// sequence is omitted.
+    fn sequences(&self) -> Cow<'_, HashMap<String, String>> {
+        Cow::Owned(self.seq.iter()
+            .map(|(gene_id, seq)| {
+                let seq_chars: Vec<char> = seq.chars().collect();
+                (gene_id.clone(), self.mask_idx.iter().filter_map(|&idx| seq_chars.get(idx)).collect())
+            })
+            .collect())
+    }
+
+    /// Returns the number of masked columns.
+    fn length(&self) -> usize {
+        self.mask_idx.len()
+    }
 }
 
 
@@ -155,8 +397,8 @@ mod tests {
 
     use super::*;
 
-    #[test]
-    fn test_from_path_valid_file() {
+    /// Write the five genome sample alignment to a temporary file.
+    fn five_genome_sample() -> tempfile::TempPath {
         let mut file = NamedTempFile::new().unwrap();
         writeln!(file, "# STOCKHOLM 1.0").unwrap();
         writeln!(file, "G1           .mAKIIN").unwrap();
@@ -172,8 +414,12 @@ mod tests {
         writeln!(file, "#=GC PP_cons ..79***").unwrap();
         writeln!(file, "#=GC RF      ..x.xx.").unwrap();
         writeln!(file, "//").unwrap();
+        file.into_temp_path()
+    }
 
-        let result = HmmAlignFile::from_path(&file.into_temp_path());
+    #[test]
+    fn test_from_path_valid_file() {
+        let result = HmmAlignFile::from_path(&five_genome_sample());
         assert!(result.is_ok());
         let result = result.unwrap();
 
@@ -203,4 +449,196 @@ mod tests {
         assert_eq!(result.pp.get("G5").unwrap(), ".*699**");
         assert_eq!(result.get_alignment("G5").unwrap(), "SKI");
     }
+
+    #[test]
+    fn test_column_conservation() {
+        // Masked columns: AAAAS, IDEDK, IIIVI
+        let mut result = HmmAlignFile::from_path(&five_genome_sample()).unwrap();
+        assert_eq!(result.column_conservation(), vec![0.8, 0.4, 0.8]);
+
+        for seq in result.seq.values_mut() {
+            seq.replace_range(4..5, "-");
+        }
+        assert_eq!(result.column_conservation(), vec![0.8, 0.0, 0.8]);
+    }
+
+    #[test]
+    fn test_from_path_spaced_blocks() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G1           .mAK IIN").unwrap();
+        writeln!(file, "#=GR G1 PP   .*79 9**").unwrap();
+        writeln!(file, "G2           maAK DIR").unwrap();
+        writeln!(file, "#=GR G2 PP   **79 ***").unwrap();
+        writeln!(file, "#=GC PP_cons ..79 ***").unwrap();
+        writeln!(file, "#=GC RF      ..x. xx.").unwrap();
+        writeln!(file, "//").unwrap();
+
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.seq.get("G1").unwrap(), ".mAKIIN");
+        assert_eq!(result.pp.get("G1").unwrap(), ".*799**");
+        assert_eq!(result.pp_cons, "..79***");
+        assert_eq!(result.mask_idx, vec![2, 4, 5]);
+        assert_eq!(result.get_alignment("G1").unwrap(), "AII");
+        assert_eq!(result.get_alignment("G2").unwrap(), "ADI");
+    }
+
+    #[test]
+    fn test_from_path_consensus_rf() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G1           .mAKIIN").unwrap();
+        writeln!(file, "#=GR G1 PP   .*799**").unwrap();
+        writeln!(file, "#=GC PP_cons ..79***").unwrap();
+        writeln!(file, "#=GC RF      ..A.IL.").unwrap();
+        writeln!(file, "//").unwrap();
+
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+        assert_eq!(result.mask_idx, vec![2, 4, 5]);
+        assert_eq!(result.insertion_columns(), vec![0, 1, 3, 6]);
+        assert_eq!(result.get_alignment("G1").unwrap(), "AII");
+    }
+
+    #[test]
+    fn test_to_masked_fasta() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G2           maAKDIR").unwrap();
+        writeln!(file, "#=GR G2 PP   **79***").unwrap();
+        writeln!(file, "G1           .mAKIIN").unwrap();
+        writeln!(file, "#=GR G1 PP   .*799**").unwrap();
+        writeln!(file, "#=GC PP_cons ..79***").unwrap();
+        writeln!(file, "#=GC RF      ..x.xx.").unwrap();
+        writeln!(file, "//").unwrap();
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("masked.faa");
+        result.to_masked_fasta(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), ">G1\nAII\n>G2\nADI\n");
+
+        let mut invalid = result.clone();
+        invalid.seq.insert("G3".to_string(), "maAK".to_string());
+        assert!(invalid.to_masked_fasta(&path).is_err());
+    }
+
+    #[test]
+    fn test_write_phylip() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G2_long_name .maAKDIR").unwrap();
+        writeln!(file, "#=GR G2_long_name PP   ***79***").unwrap();
+        writeln!(file, "G1           ..mAKIIN").unwrap();
+        writeln!(file, "#=GR G1 PP   ..*799**").unwrap();
+        writeln!(file, "#=GC PP_cons ...79***").unwrap();
+        writeln!(file, "#=GC RF      ...x.xx.").unwrap();
+        writeln!(file, "//").unwrap();
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("masked.phy");
+        result.write_phylip(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2 3\nG1 AII\nG2_long_name ADI\n");
+
+        let mut invalid = result.clone();
+        invalid.seq.insert("G 3".to_string(), ".maAKDIR".to_string());
+        assert!(invalid.write_phylip(&path).is_err());
+    }
+
+    #[test]
+    fn test_remove_gap_only_columns() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G1           .mA-I-N").unwrap();
+        writeln!(file, "#=GR G1 PP   .*7.9.*").unwrap();
+        writeln!(file, "G2           maA-D.R").unwrap();
+        writeln!(file, "#=GR G2 PP   **7.*.*").unwrap();
+        writeln!(file, "G3           .m--E-K").unwrap();
+        writeln!(file, "#=GR G3 PP   .*..*.*").unwrap();
+        writeln!(file, "#=GC PP_cons ..7.*.*").unwrap();
+        writeln!(file, "#=GC RF      ..xxxxx").unwrap();
+        writeln!(file, "//").unwrap();
+        let mut result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+
+        assert_eq!(result.remove_gap_only_columns(), 2);
+        assert_eq!(result.mask_idx, vec![2, 4, 6]);
+        assert_eq!(result.mask, vec![false, false, true, false, true, false, true]);
+        assert_eq!(result.rf_mask, vec![false, false, true, true, true, true, true]);
+        assert_eq!(result.insertion_columns(), vec![0, 1]);
+        assert_eq!(result.get_alignment("G1").unwrap(), "AIN");
+        assert_eq!(result.get_alignment("G3").unwrap(), "-EK");
+        assert_eq!(result.remove_gap_only_columns(), 0);
+    }
+
+    #[test]
+    fn test_trim_by_gap_fraction() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G1           .mAKIIN").unwrap();
+        writeln!(file, "#=GR G1 PP   .*799**").unwrap();
+        writeln!(file, "G2           .m-KD-R").unwrap();
+        writeln!(file, "#=GR G2 PP   .*.**.*").unwrap();
+        writeln!(file, "G3           .m-KE.K").unwrap();
+        writeln!(file, "#=GR G3 PP   .*.**.*").unwrap();
+        writeln!(file, "G4           .mSKD-L").unwrap();
+        writeln!(file, "#=GR G4 PP   .*699.*").unwrap();
+        writeln!(file, "#=GC PP_cons ..79***").unwrap();
+        writeln!(file, "#=GC RF      ..x.xxx").unwrap();
+        writeln!(file, "//").unwrap();
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+
+        assert_eq!(result.column_occupancy(), vec![0.5, 1.0, 0.25, 1.0]);
+
+        let trimmed = result.trim_by_gap_fraction(0.5);
+        assert_eq!(trimmed["G1"], "AIN");
+        assert_eq!(trimmed["G2"], "-DR");
+        assert_eq!(trimmed["G4"], "SDL");
+
+        let trimmed = result.trim_by_gap_fraction(0.0);
+        assert_eq!(trimmed["G1"], "IN");
+        assert_eq!(trimmed["G3"], "EK");
+
+        let trimmed = result.trim_by_gap_fraction(1.0);
+        assert_eq!(trimmed["G1"], "AIIN");
+    }
+
+    #[test]
+    fn test_identity_matrix() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G3           .mAKDI-").unwrap();
+        writeln!(file, "#=GR G3 PP   .*799*.").unwrap();
+        writeln!(file, "G1           .mAKIIN").unwrap();
+        writeln!(file, "#=GR G1 PP   .*799**").unwrap();
+        writeln!(file, "G2           maaKDIR").unwrap();
+        writeln!(file, "#=GR G2 PP   **79***").unwrap();
+        writeln!(file, "G4           .m-K--N").unwrap();
+        writeln!(file, "#=GR G4 PP   .*.*..*").unwrap();
+        writeln!(file, "#=GC PP_cons ..79***").unwrap();
+        writeln!(file, "#=GC RF      ..x.xxx").unwrap();
+        writeln!(file, "//").unwrap();
+        let result = HmmAlignFile::from_path(&file.into_temp_path()).unwrap();
+
+        let (gene_ids, matrix) = result.identity_matrix();
+        assert_eq!(gene_ids, vec!["G1", "G2", "G3", "G4"]);
+        // G1: AIIN, G2: ADIR, G3: ADI-, G4: ---N
+        assert_eq!(matrix[0], vec![100.0, 50.0, 100.0 * 2.0 / 3.0, 100.0]);
+        assert_eq!(matrix[1], vec![50.0, 100.0, 100.0, 0.0]);
+        assert_eq!(matrix[2][0], matrix[0][2]);
+        assert_eq!(matrix[2][3], 0.0);
+        assert_eq!(matrix[3][3], 100.0);
+    }
+
+    #[test]
+    fn test_from_path_invalid_line() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# STOCKHOLM 1.0").unwrap();
+        writeln!(file, "G1").unwrap();
+        writeln!(file, "//").unwrap();
+
+        match HmmAlignFile::from_path(&file.into_temp_path()) {
+            Err(ProleError::Exit(msg)) => assert_eq!(msg, "Error parsing: G1"),
+            _ => panic!("Expected a parsing error"),
+        }
+    }
 }
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufReader;
 use std::path::Path;
 
 use flate2::read::GzDecoder;
@@ -7,6 +8,8 @@ use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::error::{ProleError, ProleResult};
+use crate::hmm::hmm_model::ModelCutoffs;
+use crate::util::parse::{FromLine, parse_lines};
 
 /// Methods for loading a HMMER output file formatted using `--tblout`.
 ///
@@ -17,6 +20,7 @@ use crate::error::{ProleError, ProleResult};
 /// CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18;partial=00;start_type=ATG;rbs_motif=None;rbs_spacer=None;gc_cont=0.492
 /// CAKWUX010000058.1_8  -          TIGR00002            TIGR00002    4.7e-30  100.6   0.0   8.2e-30   99.8   0.0   1.4   1   0   0   1   1   1   1 # 6333 # 6881 # -1 # ID=58_8;partial=00;start_type=ATG;rbs_motif=AAAA;rbs_spacer=11bp;gc_cont=0.599
 /// ```
+#[derive(Debug, Clone)]
 pub struct HmmSearchFile(pub Vec<HmmSearchHit>);
 
 
@@ -32,15 +36,21 @@ impl HmmSearchFile {
     /// let pfam_file = HmmSearchFile::from_bufreader(reader).unwrap();
     /// ```
     pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
-        let mut out = vec![];
-        for line in buf.lines() {
-            let line = line.map_err(ProleError::IoError)?;
-            if line.starts_with('#') || line.is_empty() {
-                continue;
-            }
-            out.push(HmmSearchHit::from_string(&line)?);
-        }
-        Ok(Self(out))
+        Ok(Self(parse_lines(buf)?))
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [HmmSearchFile], the reader
+    /// is buffered internally.
+    ///
+    /// ```
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    ///
+    /// let content = "gene_1 - TIGR00001 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 -\n";
+    /// let hits = HmmSearchFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(hits.0.len(), 1);
+    /// ```
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
     }
 
     /// Read the content from a [Path] and parse it into a [HmmSearchFile].
@@ -73,10 +83,75 @@ impl HmmSearchFile {
         let reader = BufReader::new(gz);
         Self::from_bufreader(reader)
     }
+
+    /// Returns the hits whose `full_seq_score` meets the gathering threshold of their query.
+    ///
+    /// The `ga` map is keyed by `query_name` (see [crate::hmm::hmm_model::parse_ga_cutoffs]),
+    /// hits for a query without a threshold are discarded.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmm_model::parse_ga_cutoffs;
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    ///
+    /// let ga = parse_ga_cutoffs(Path::new("/path/to/tigrfam.hmm")).unwrap();
+    /// let hits = HmmSearchFile::from_path(Path::new("/path/to/file")).unwrap();
+    /// let passing = hits.apply_gathering_thresholds(&ga);
+    /// ```
+    pub fn apply_gathering_thresholds(&self, ga: &ModelCutoffs) -> Vec<&HmmSearchHit> {
+        self.0.iter()
+            .filter(|hit| ga.get(&hit.query_name).map_or(false, |&cutoff| hit.full_seq_score >= cutoff))
+            .collect()
+    }
+
+    /// Return the best hit for each target (i.e. the highest full sequence score, ties are resolved
+    /// by the lowest e-value, and then the order in the file).
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    ///
+    /// let hits = HmmSearchFile::from_path(Path::new("/path/to/file")).unwrap();
+    /// let best = hits.best_hit_per_target();
+    /// ```
+    pub fn best_hit_per_target(&self) -> HashMap<&str, &HmmSearchHit> {
+        let mut out: HashMap<&str, &HmmSearchHit> = HashMap::new();
+        for hit in &self.0 {
+            if out.get(hit.target_name.as_str()).map_or(true, |best| hit.is_better_than(best)) {
+                out.insert(hit.target_name.as_str(), hit);
+            }
+        }
+        out
+    }
+
+    /// Return the best hit for each model (`query_name`) after discarding hits below the
+    /// model's bit score cutoff (see [HmmSearchFile::apply_gathering_thresholds]).
+    /// The best hit is selected as per [HmmSearchFile::best_hit_per_target], and models
+    /// without a passing hit are absent.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmm_model::parse_ga_cutoffs;
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    ///
+    /// let cutoffs = parse_ga_cutoffs(Path::new("/path/to/tigrfam.hmm")).unwrap();
+    /// let hits = HmmSearchFile::from_path(Path::new("/path/to/file")).unwrap();
+    /// let best = hits.best_passing_hit_per_model(&cutoffs);
+    /// ```
+    pub fn best_passing_hit_per_model(&self, cutoffs: &ModelCutoffs) -> HashMap<String, &HmmSearchHit> {
+        let mut out: HashMap<String, &HmmSearchHit> = HashMap::new();
+        for hit in self.apply_gathering_thresholds(cutoffs) {
+            if out.get(&hit.query_name).map_or(true, |best| hit.is_better_than(best)) {
+                out.insert(hit.query_name.clone(), hit);
+            }
+        }
+        out
+    }
 }
 
 
 /// A hit within the [HmmSearchFile] struct.
+#[derive(Debug, Clone)]
 pub struct HmmSearchHit {
     /// The name of the target sequence or profile.
     pub target_name: String,
@@ -127,6 +202,7 @@ pub struct HmmSearchHit {
 
 
 lazy_static! {
+    static ref RE_PRODIGAL_DESC: Regex = Regex::new(r"^#\s+(\d+)\s+#\s+(\d+)\s+#\s+(-?1)\s+#").unwrap();
     static ref RE_HMM_SEARCH_LINE: Regex = Regex::new(r"^([^\s]+)\s+([^\s]+)\s+([^\s]+)\s+([^\s]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+([\d.e+-]+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(.+)$").unwrap();
 }
 
@@ -178,6 +254,62 @@ impl HmmSearchHit {
             description: hits[19].to_string(),
         })
     }
+
+    /// Returns true if this hit has a higher `full_seq_score` than `other`, or an equal score
+    /// with a lower `full_seq_evalue`.
+    fn is_better_than(&self, other: &HmmSearchHit) -> bool {
+        self.full_seq_score > other.full_seq_score ||
+            (self.full_seq_score == other.full_seq_score && self.full_seq_evalue < other.full_seq_evalue)
+    }
+
+    /// Returns the (start, end, strand) coordinates of the target gene on its contig, if the
+    /// description was written by Prodigal (e.g. `# 15227 # 15421 # -1 # ID=27_18;...`).
+    ///
+    /// ```
+    /// use prole::hmm::hmmsearch_file::HmmSearchHit;
+    ///
+    /// let hit = HmmSearchHit::from_string("CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18").unwrap();
+    /// assert_eq!(hit.prodigal_coordinates(), Some((15227, 15421, -1)));
+    /// ```
+    pub fn prodigal_coordinates(&self) -> Option<(u32, u32, i8)> {
+        let hits = RE_PRODIGAL_DESC.captures(&self.description)?;
+        Some((hits[1].parse().ok()?, hits[2].parse().ok()?, hits[3].parse().ok()?))
+    }
+
+    /// Estimate the fraction of the query model covered by this hit.
+    ///
+    /// As the tblout format does not report alignment coordinates, this is approximated as
+    /// the length of the target protein (from the Prodigal coordinates) divided by the number of
+    /// envelopes (`env`), relative to the model length in `hmm_lengths` (keyed by `query_name`).
+    /// The result is capped at 1.0.
+    ///
+    /// Returns None if the query model is missing from `hmm_lengths`, the description does not
+    /// contain Prodigal coordinates, or no envelopes were defined.
+    pub fn estimated_coverage(&self, hmm_lengths: &HashMap<String, u32>) -> Option<f64> {
+        let hmm_length = *hmm_lengths.get(&self.query_name)?;
+        let (start, end, _strand) = self.prodigal_coordinates()?;
+        if hmm_length == 0 || self.env == 0 || end < start {
+            return None;
+        }
+        let protein_length = f64::from(end - start + 1) / 3.0;
+        let coverage = protein_length / f64::from(self.env) / f64::from(hmm_length);
+        Some(coverage.min(1.0))
+    }
+}
+
+
+impl std::fmt::Display for HmmSearchHit {
+    /// A one-line summary of the hit, e.g. `CAKWUX010000027.1_18 TIGR00001 evalue=1.9e-26 score=89.3`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {} evalue={:e} score={}", self.target_name, self.query_name, self.full_seq_evalue, self.full_seq_score)
+    }
+}
+
+
+impl FromLine for HmmSearchHit {
+    fn from_line(line: &str) -> ProleResult<Self> {
+        Self::from_string(line)
+    }
 }
 
 
@@ -192,6 +324,8 @@ mod tests {
     use super::*;
 
     #[test]
+    // The empty line is written explicitly, as skipping it is part of the test
+    #[allow(clippy::writeln_empty_string)]
     fn test_from_path_valid_file() {
         let mut file = NamedTempFile::new().unwrap();
         writeln!(file, "#to be ignored").unwrap();
@@ -226,6 +360,22 @@ mod tests {
         assert_eq!(&result.0[1].target_name, "CAKWUX010000041.1_17");
     }
 
+    #[test]
+    fn test_apply_gathering_thresholds() {
+        let file = HmmSearchFile(vec![
+            HmmSearchHit::from_string("CAKWUX010000001.1_73 -          TIGR00046            TIGR00046    7.9e-36  120.7   0.0   9.6e-36  120.4   0.0   1.0   1   0   0   1   1   1   1 # 101713 # 102426 # 1 # ID=1_73").unwrap(),
+            HmmSearchHit::from_string("CAKWUX010000041.1_17 -          TIGR00054            TIGR00054    8.9e-62  206.4   0.0   1.1e-61  206.0   0.0   1.0   1   0   0   1   1   1   1 # 20284 # 21807 # 1 # ID=41_17").unwrap(),
+            HmmSearchHit::from_string("CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18").unwrap(),
+        ]);
+        let mut ga = HashMap::new();
+        ga.insert("TIGR00046".to_string(), 120.7);
+        ga.insert("TIGR00054".to_string(), 250.0);
+
+        let result = file.apply_gathering_thresholds(&ga);
+        assert_eq!(result.len(), 1);
+        assert_eq!(&result[0].target_name, "CAKWUX010000001.1_73");
+    }
+
     #[test]
     fn test_from_path_invalid_file() {
         let mut file = NamedTempFile::new().unwrap();
@@ -288,6 +438,76 @@ mod tests {
         assert_eq!(hit.description, "# 2754 # 3044 # 1 # ID=58_4;partial=00;start_type=ATG;rbs_motif=TAAAAA;rbs_spacer=4bp;gc_cont=0.471");
     }
 
+    #[test]
+    fn test_prodigal_coordinates() {
+        let hit = HmmSearchHit::from_string("CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18;partial=00").unwrap();
+        assert_eq!(hit.prodigal_coordinates(), Some((15227, 15421, -1)));
+        let hit = HmmSearchHit::from_string("CAKWUX010000001.1_73 -          TIGR00046            TIGR00046    7.9e-36  120.7   0.0   9.6e-36  120.4   0.0   1.0   1   0   0   1   1   1   1 # 101713 # 102426 # 1 # ID=1_73").unwrap();
+        assert_eq!(hit.prodigal_coordinates(), Some((101713, 102426, 1)));
+        let hit = HmmSearchHit::from_string("CAKWUX010000001.1_73 -          TIGR00046            TIGR00046    7.9e-36  120.7   0.0   9.6e-36  120.4   0.0   1.0   1   0   0   1   1   1   1 some protein").unwrap();
+        assert_eq!(hit.prodigal_coordinates(), None);
+    }
+
+    #[test]
+    fn test_estimated_coverage() {
+        // 195 nt -> 65 aa
+        let hit = HmmSearchHit::from_string("CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18").unwrap();
+        let mut hmm_lengths = HashMap::new();
+        hmm_lengths.insert("TIGR00001".to_string(), 130);
+        assert_eq!(hit.estimated_coverage(&hmm_lengths), Some(0.5));
+        hmm_lengths.insert("TIGR00001".to_string(), 60);
+        assert_eq!(hit.estimated_coverage(&hmm_lengths), Some(1.0));
+        assert_eq!(hit.estimated_coverage(&HashMap::new()), None);
+
+        // Two envelopes halve the estimate
+        let hit = HmmSearchHit::from_string("CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   2   1   1   1 # 15227 # 15421 # -1 # ID=27_18").unwrap();
+        hmm_lengths.insert("TIGR00001".to_string(), 65);
+        assert_eq!(hit.estimated_coverage(&hmm_lengths), Some(0.5));
+    }
+
+    #[test]
+    fn test_best_hit_per_target() {
+        let hits = HmmSearchFile(vec![
+            HmmSearchHit::from_string("gene_1 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_1 -          TIGR00002            TIGR00002    1.9e-30   99.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_1 -          TIGR00003            TIGR00003    1.9e-31   99.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_1 -          TIGR00004            TIGR00004    1.9e-31   99.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_2 -          TIGR00001            TIGR00001    1.9e-26   50.0   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+        ]);
+        let best = hits.best_hit_per_target();
+        assert_eq!(best.len(), 2);
+        assert_eq!(best["gene_1"].query_name, "TIGR00003");
+        assert_eq!(best["gene_2"].query_name, "TIGR00001");
+    }
+
+    #[test]
+    fn test_best_passing_hit_per_model() {
+        let hits = HmmSearchFile(vec![
+            HmmSearchHit::from_string("gene_1 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_2 -          TIGR00001            TIGR00001    1.9e-30   99.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_3 -          TIGR00002            TIGR00002    1.9e-31   20.0   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_4 -          TIGR00002            TIGR00002    1.9e-31   40.0   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+            HmmSearchHit::from_string("gene_5 -          TIGR00003            TIGR00003    1.9e-31   10.0   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 1").unwrap(),
+        ]);
+        let cutoffs = ModelCutoffs::from([
+            ("TIGR00001".to_string(), 95.0),
+            ("TIGR00002".to_string(), 15.0),
+            ("TIGR00003".to_string(), 15.0),
+        ]);
+        let best = hits.best_passing_hit_per_model(&cutoffs);
+        assert_eq!(best.len(), 2);
+        assert_eq!(best["TIGR00001"].target_name, "gene_2");
+        assert_eq!(best["TIGR00002"].target_name, "gene_4");
+        assert!(!best.contains_key("TIGR00003"));
+    }
+
+    #[test]
+    fn test_display() {
+        let string = "CAKWUX010000027.1_18 -          TIGR00001            TIGR00001    1.9e-26   89.3   7.9   2.1e-26   89.2   7.9   1.0   1   0   0   1   1   1   1 # 15227 # 15421 # -1 # ID=27_18;partial=00;start_type=ATG;rbs_motif=None;rbs_spacer=None;gc_cont=0.492";
+        let hit = HmmSearchHit::from_string(string).unwrap();
+        assert_eq!(hit.to_string(), "CAKWUX010000027.1_18 TIGR00001 evalue=1.9e-26 score=89.3");
+    }
+
     #[test]
     fn test_from_string_invalid_format() {
         let string = "CAKWUX010000001.1_1       1    263      1    ";
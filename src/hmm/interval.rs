@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::hmm::hmmsearch_file::{HmmSearchFile, HmmSearchHit};
+use crate::hmm::pypfam_file::{PyPfamFile, PyPfamHit};
+
+/// A static interval tree, stored as an implicit binary search tree over the intervals sorted
+/// by start. Each node (the midpoint of a range) records the maximum end within its subtree.
+#[derive(Debug, Clone)]
+struct IntervalTree<'a, T> {
+    intervals: Vec<(usize, usize, &'a T)>,
+    max_end: Vec<usize>,
+}
+
+impl<'a, T> IntervalTree<'a, T> {
+    fn new(mut intervals: Vec<(usize, usize, &'a T)>) -> Self {
+        intervals.sort_by_key(|&(start, end, _)| (start, end));
+        let mut tree = Self { max_end: vec![0; intervals.len()], intervals };
+        tree.build(0, tree.intervals.len());
+        tree
+    }
+
+    /// Populate `max_end` for the subtree spanning `[lo, hi)`, returning its maximum end.
+    fn build(&mut self, lo: usize, hi: usize) -> usize {
+        if lo >= hi {
+            return 0;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let max_end = self.intervals[mid].1
+            .max(self.build(lo, mid))
+            .max(self.build(mid + 1, hi));
+        self.max_end[mid] = max_end;
+        max_end
+    }
+
+    /// Collect the intervals in the subtree spanning `[lo, hi)` that overlap `[start, end]`.
+    fn query(&self, lo: usize, hi: usize, start: usize, end: usize, out: &mut Vec<&'a T>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_end[mid] < start {
+            return;
+        }
+        self.query(lo, mid, start, end, out);
+        let (mid_start, mid_end, value) = self.intervals[mid];
+        if mid_start <= end {
+            if mid_end >= start {
+                out.push(value);
+            }
+            self.query(mid + 1, hi, start, end, out);
+        }
+    }
+}
+
+
+/// An index of hits by their (1-based, inclusive) coordinates on each contig, for finding the
+/// hits that overlap a region without a linear scan.
+#[derive(Debug, Clone)]
+pub struct HitIndex<'a, T> {
+    trees: HashMap<String, IntervalTree<'a, T>>,
+}
+
+impl<'a, T> HitIndex<'a, T> {
+    /// Create a [HitIndex] from `(contig, start, end, hit)` tuples, where the coordinates are
+    /// 1-based and inclusive. The start and end are swapped if they are reversed.
+    pub fn new<I: IntoIterator<Item=(String, usize, usize, &'a T)>>(hits: I) -> Self {
+        let mut by_contig: HashMap<String, Vec<(usize, usize, &'a T)>> = HashMap::new();
+        for (contig, start, end, hit) in hits {
+            by_contig.entry(contig).or_default().push((start.min(end), start.max(end), hit));
+        }
+        Self {
+            trees: by_contig.into_iter().map(|(contig, intervals)| (contig, IntervalTree::new(intervals))).collect(),
+        }
+    }
+
+    /// Returns the hits on `contig` that overlap the region `[start, end]` (1-based, inclusive),
+    /// sorted by their start coordinate.
+    pub fn query(&self, contig: &str, start: usize, end: usize) -> Vec<&'a T> {
+        let mut out = Vec::new();
+        if let Some(tree) = self.trees.get(contig) {
+            tree.query(0, tree.intervals.len(), start.min(end), start.max(end), &mut out);
+        }
+        out
+    }
+
+    /// Returns the number of hits in the index.
+    pub fn len(&self) -> usize {
+        self.trees.values().map(|tree| tree.intervals.len()).sum()
+    }
+
+    /// Returns true if there are no hits in the index.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> HitIndex<'a, HmmSearchHit> {
+    /// Index the hits in a [HmmSearchFile] by the location of the target gene on its contig,
+    /// using the Prodigal coordinates (see [HmmSearchHit::prodigal_coordinates]). The contig is
+    /// the target name without the Prodigal gene suffix (e.g. `contig_1_18` is on `contig_1`).
+    ///
+    /// Hits without Prodigal coordinates are not indexed.
+    ///
+    /// ```
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    /// use prole::hmm::interval::HitIndex;
+    ///
+    /// let content = "contig_1_1 - TIGR00001 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 # 100 # 400 # 1 # ID=1_1\n";
+    /// let hits = HmmSearchFile::from_reader(content.as_bytes()).unwrap();
+    /// let index = HitIndex::from_hmmsearch(&hits);
+    /// assert_eq!(index.query("contig_1", 350, 500).len(), 1);
+    /// assert!(index.query("contig_1", 401, 500).is_empty());
+    /// ```
+    pub fn from_hmmsearch(file: &'a HmmSearchFile) -> Self {
+        Self::new(file.0.iter().filter_map(|hit| {
+            let (start, end, _strand) = hit.prodigal_coordinates()?;
+            let contig = hit.target_name.rsplit_once('_').map_or(hit.target_name.as_str(), |(contig, _)| contig);
+            Some((contig.to_string(), start as usize, end as usize, hit))
+        }))
+    }
+}
+
+impl<'a> HitIndex<'a, PyPfamHit> {
+    /// Index the hits in a [PyPfamFile] by their alignment coordinates (`align_start` to
+    /// `align_end`) on the target sequence (`seq_id`).
+    ///
+    /// ```
+    /// use prole::hmm::interval::HitIndex;
+    /// use prole::hmm::pypfam_file::PyPfamFile;
+    ///
+    /// let content = "gene_1 1 263 2 265 PF02896.19 PEP-utilizers_C Domain 72 292 294 252.7 5.5e-76 1 CL0151\n";
+    /// let hits = PyPfamFile::from_reader(content.as_bytes()).unwrap();
+    /// let index = HitIndex::from_pypfam(&hits);
+    /// assert_eq!(index.query("gene_1", 263, 300).len(), 1);
+    /// ```
+    pub fn from_pypfam(file: &'a PyPfamFile) -> Self {
+        Self::new(file.0.iter().map(|hit| (hit.seq_id.clone(), hit.align_start as usize, hit.align_end as usize, hit)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let intervals = [(10, 20), (15, 25), (30, 40), (5, 100), (50, 60), (41, 41), (22, 12)];
+        let names: Vec<String> = (0..intervals.len()).map(|i| format!("h{}", i)).collect();
+        let index = HitIndex::new(intervals.iter().zip(names.iter())
+            .map(|(&(start, end), name)| ("c1".to_string(), start, end, name))
+            .chain([("c2".to_string(), 1, 1000, &names[0])]));
+        assert_eq!(index.len(), 8);
+
+        let query = |start, end| index.query("c1", start, end).into_iter().map(|x| x.as_str()).collect::<Vec<&str>>();
+        assert_eq!(query(1, 4), Vec::<&str>::new());
+        assert_eq!(query(21, 21), vec!["h3", "h6", "h1"]);
+        assert_eq!(query(26, 29), vec!["h3"]);
+        assert_eq!(query(40, 41), vec!["h3", "h2", "h5"]);
+        assert_eq!(query(61, 99), vec!["h3"]);
+        assert_eq!(query(101, 200), Vec::<&str>::new());
+        assert_eq!(query(60, 50), vec!["h3", "h4"]);
+        assert!(index.query("c3", 1, 1000).is_empty());
+
+        // Compare against a linear scan
+        for start in 0..110 {
+            for end in start..110 {
+                let mut expected: Vec<&str> = intervals.iter().zip(names.iter())
+                    .filter(|(&(a, b), _)| a.min(b) <= end && a.max(b) >= start)
+                    .map(|(_, name)| name.as_str())
+                    .collect();
+                let mut result = query(start, end);
+                expected.sort();
+                result.sort();
+                assert_eq!(result, expected, "{}-{}", start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_hmmsearch() {
+        let content = [
+            "contig_1_1 - TIGR00001 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 # 100 # 400 # 1 # ID=1_1",
+            "contig_1_2 - TIGR00002 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 # 500 # 800 # -1 # ID=1_2",
+            "contig_2_1 - TIGR00003 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 # 100 # 400 # 1 # ID=2_1",
+            "contig_2_2 - TIGR00004 - 1.9e-26 89.3 7.9 2.1e-26 89.2 7.9 1.0 1 0 0 1 1 1 1 no coordinates",
+        ].join("\n");
+        let hits = HmmSearchFile::from_reader(content.as_bytes()).unwrap();
+        let index = HitIndex::from_hmmsearch(&hits);
+        assert_eq!(index.len(), 3);
+
+        let query = |contig, start, end| index.query(contig, start, end).into_iter().map(|x| x.query_name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(query("contig_1", 1, 1000), vec!["TIGR00001", "TIGR00002"]);
+        assert_eq!(query("contig_1", 400, 500), vec!["TIGR00001", "TIGR00002"]);
+        assert_eq!(query("contig_1", 401, 499), Vec::<&str>::new());
+        assert_eq!(query("contig_2", 1, 1000), vec!["TIGR00003"]);
+    }
+}
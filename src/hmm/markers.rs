@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::hmm::hmm_model::ModelCutoffs;
+use crate::hmm::hmmsearch_file::HmmSearchFile;
+
+/// A set of expected marker models (by `query_name`), e.g. single-copy marker genes.
+#[derive(Debug, Clone, Default)]
+pub struct MarkerSet(pub HashSet<String>);
+
+/// The result of scoring a genome against a [MarkerSet].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkerScore {
+    /// The fraction of markers (`[0, 1]`) with at least one hit.
+    pub completeness: f64,
+    /// The number of additional copies of markers with multiple hits, as a fraction of the
+    /// number of markers (this may exceed 1).
+    pub contamination: f64,
+    /// The markers (sorted) that have no hits.
+    pub missing: Vec<String>,
+    /// The markers (sorted) that are hit by more than one target.
+    pub multi_copy: Vec<String>,
+}
+
+impl MarkerSet {
+    /// Score the hits from a single genome against this [MarkerSet].
+    ///
+    /// Only hits that meet the cutoff of their model are considered (see
+    /// [HmmSearchFile::apply_gathering_thresholds]), and a marker is only counted once for each
+    /// distinct target. Hits to models not in the set are ignored.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::hmm::hmm_model::parse_ga_cutoffs;
+    /// use prole::hmm::hmmsearch_file::HmmSearchFile;
+    /// use prole::hmm::markers::MarkerSet;
+    ///
+    /// let cutoffs = parse_ga_cutoffs(Path::new("/path/to/markers.hmm")).unwrap();
+    /// let markers = MarkerSet(cutoffs.keys().cloned().collect());
+    /// let hits = HmmSearchFile::from_path(Path::new("/path/to/tblout.tsv")).unwrap();
+    /// let score = markers.score(&hits, &cutoffs);
+    /// println!("{:.2}% complete", score.completeness * 100.0);
+    /// ```
+    pub fn score(&self, hits: &HmmSearchFile, cutoffs: &ModelCutoffs) -> MarkerScore {
+        let mut targets: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for hit in hits.apply_gathering_thresholds(cutoffs) {
+            if self.0.contains(&hit.query_name) {
+                targets.entry(hit.query_name.as_str()).or_default().insert(hit.target_name.as_str());
+            }
+        }
+
+        let mut missing: Vec<String> = self.0.iter()
+            .filter(|marker| !targets.contains_key(marker.as_str()))
+            .cloned()
+            .collect();
+        missing.sort();
+        let mut multi_copy: Vec<String> = targets.iter()
+            .filter(|(_, hits)| hits.len() > 1)
+            .map(|(marker, _)| marker.to_string())
+            .collect();
+        multi_copy.sort();
+
+        let (completeness, contamination) = if self.0.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let n_extra: usize = targets.values().map(|hits| hits.len() - 1).sum();
+            (targets.len() as f64 / self.0.len() as f64, n_extra as f64 / self.0.len() as f64)
+        };
+
+        MarkerScore { completeness, contamination, missing, multi_copy }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let markers = MarkerSet(HashSet::from(["M1".to_string(), "M2".to_string(), "M3".to_string()]));
+        let cutoffs = ModelCutoffs::from([
+            ("M1".to_string(), 10.0),
+            ("M2".to_string(), 10.0),
+            ("M3".to_string(), 10.0),
+            ("OTHER".to_string(), 10.0),
+        ]);
+        let content = [
+            "gene_1 - M1 - 1e-10 50.0 0.0 1e-10 50.0 0.0 1.0 1 0 0 1 1 1 1 -",
+            "gene_2 - M1 - 1e-10 40.0 0.0 1e-10 40.0 0.0 1.0 1 0 0 1 1 1 1 -",
+            "gene_2 - M1 - 1e-10 40.0 0.0 1e-10 40.0 0.0 1.0 1 0 0 1 1 1 1 -",
+            "gene_3 - M2 - 1e-10 30.0 0.0 1e-10 30.0 0.0 1.0 1 0 0 1 1 1 1 -",
+            "gene_4 - M3 - 1e-1 5.0 0.0 1e-1 5.0 0.0 1.0 1 0 0 1 1 1 1 -",
+            "gene_5 - OTHER - 1e-10 50.0 0.0 1e-10 50.0 0.0 1.0 1 0 0 1 1 1 1 -",
+        ].join("\n");
+        let hits = HmmSearchFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+
+        let score = markers.score(&hits, &cutoffs);
+        assert!((score.completeness - 2.0 / 3.0).abs() < 1e-12);
+        assert!((score.contamination - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(score.missing, vec!["M3".to_string()]);
+        assert_eq!(score.multi_copy, vec!["M1".to_string()]);
+
+        let empty = MarkerSet::default().score(&hits, &cutoffs);
+        assert_eq!(empty.completeness, 0.0);
+        assert_eq!(empty.contamination, 0.0);
+    }
+}
@@ -1,3 +1,10 @@
 pub mod pypfam_file;
 pub mod hmmsearch_file;
-pub mod hmmalign_file;
\ No newline at end of file
+pub mod hmmalign_file;
+pub mod clustal_file;
+pub mod alignment;
+pub mod hmm_model;
+pub mod annotate;
+pub mod markers;
+pub mod concat;
+pub mod interval;
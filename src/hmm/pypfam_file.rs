@@ -1,17 +1,14 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufReader;
 use std::path::Path;
 
 use flate2::read::GzDecoder;
-use lazy_static::lazy_static;
-use regex::Regex;
 
 use crate::error::{ProleError, ProleResult};
+use crate::util::parse::{FromLine, parse_lines};
 
-lazy_static! {
-    static ref RE_PFAM_LINE: Regex = Regex::new(r"^([^\s]+)\s+(\d+)\s+(\d+)\s+(\d+)\s+(\d+)\s+([^\s]+)\s+([^\s]+)\s+([^\s]+)\s+(\d+)\s+(\d+)\s+(\d+)\s+([+-.e\d]+)\s+([+-.e\d]+)\s+([+-.e\d]+)\s+([^\s]+)\s*$").unwrap();
-}
-
+/// The number of whitespace delimited columns in a PyPfam output line.
+const PFAM_COLUMNS: usize = 15;
 
 /// Methods for loading a PyPfam HMMER output file.
 ///
@@ -22,6 +19,7 @@ lazy_static! {
 /// CAKWUX010000001.1_1       1    263      1    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151
 /// CAKWUX010000001.1_10     34    157     33    160 PF14622.7   Ribonucleas_3_3   Family     2   124   128     82.4     4e-24   1 CL0539
 /// ```
+#[derive(Debug, Clone)]
 pub struct PyPfamFile(pub Vec<PyPfamHit>);
 
 
@@ -37,15 +35,21 @@ impl PyPfamFile {
     /// let pfam_file = PyPfamFile::from_bufreader(reader).unwrap();
     /// ```
     pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
-        let mut out = vec![];
-        for line in buf.lines() {
-            let line = line.map_err(ProleError::IoError)?;
-            if line.starts_with('#') || line.is_empty() {
-                continue;
-            }
-            out.push(PyPfamHit::from_string(&line)?);
-        }
-        Ok(Self(out))
+        Ok(Self(parse_lines(buf)?))
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [PyPfamFile], the reader
+    /// is buffered internally.
+    ///
+    /// ```
+    /// use prole::hmm::pypfam_file::PyPfamFile;
+    ///
+    /// let content = "CAKWUX010000001.1_1       1    263      1    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151\n";
+    /// let pfam_file = PyPfamFile::from_reader(content.as_bytes()).unwrap();
+    /// assert_eq!(pfam_file.0.len(), 1);
+    /// ```
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
     }
 
     /// Read the content from a [Path] and parse it into a [PyPfamFile].
@@ -78,10 +82,80 @@ impl PyPfamFile {
         let reader = BufReader::new(gz);
         Self::from_bufreader(reader)
     }
+
+    /// Merge the hits of the same model (`hmm_acc`) on the same sequence (`seq_id`) that overlap,
+    /// or are separated by at most `max_gap` residues, e.g. a domain reported as two fragments.
+    ///
+    /// Each [MergedHit] spans from the first `align_start` to the last `align_end` of its
+    /// fragments. The bit score is the maximum of the fragments and the e-value is the minimum,
+    /// as the scores of separately aligned fragments cannot be combined.
+    ///
+    /// The merged hits are sorted by `seq_id`, then `align_start`, then `hmm_acc`.
+    ///
+    /// ```
+    /// use prole::hmm::pypfam_file::PyPfamFile;
+    ///
+    /// let content = "gene_1 1 100 1 100 PF00001.1 A Domain 1 100 200 50.0 1e-10 1 No_clan\ngene_1 105 200 105 200 PF00001.1 A Domain 101 200 200 60.0 1e-12 1 No_clan\n";
+    /// let merged = PyPfamFile::from_reader(content.as_bytes()).unwrap().merge_adjacent(5);
+    /// assert_eq!(merged.len(), 1);
+    /// assert_eq!((merged[0].align_start, merged[0].align_end, merged[0].n_fragments), (1, 200, 2));
+    /// ```
+    pub fn merge_adjacent(&self, max_gap: u32) -> Vec<MergedHit> {
+        let mut hits: Vec<&PyPfamHit> = self.0.iter().collect();
+        hits.sort_by(|a, b| {
+            (&a.seq_id, &a.hmm_acc, a.align_start, a.align_end).cmp(&(&b.seq_id, &b.hmm_acc, b.align_start, b.align_end))
+        });
+
+        let mut out: Vec<MergedHit> = Vec::new();
+        for hit in hits {
+            if let Some(last) = out.last_mut() {
+                let is_same = last.seq_id == hit.seq_id && last.hmm_acc == hit.hmm_acc;
+                if is_same && u64::from(hit.align_start) <= u64::from(last.align_end) + u64::from(max_gap) + 1 {
+                    last.align_end = last.align_end.max(hit.align_end);
+                    last.bit_score = last.bit_score.max(hit.bit_score);
+                    last.e_value = last.e_value.min(hit.e_value);
+                    last.n_fragments += 1;
+                    continue;
+                }
+            }
+            out.push(MergedHit {
+                seq_id: hit.seq_id.clone(),
+                hmm_acc: hit.hmm_acc.clone(),
+                align_start: hit.align_start,
+                align_end: hit.align_end,
+                bit_score: hit.bit_score,
+                e_value: hit.e_value,
+                n_fragments: 1,
+            });
+        }
+        out.sort_by(|a, b| (&a.seq_id, a.align_start, &a.hmm_acc).cmp(&(&b.seq_id, b.align_start, &b.hmm_acc)));
+        out
+    }
+}
+
+
+/// One or more [PyPfamHit]s of the same model merged by [PyPfamFile::merge_adjacent].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedHit {
+    /// The gene identifier of the target sequence.
+    pub seq_id: String,
+    /// The accession of the HMM (e.g. `PF02896.19`).
+    pub hmm_acc: String,
+    /// The position in the target sequence at which the first fragment starts.
+    pub align_start: u32,
+    /// The position in the target sequence at which the last fragment ends.
+    pub align_end: u32,
+    /// The maximum bit score of the fragments.
+    pub bit_score: f64,
+    /// The minimum expectation value of the fragments.
+    pub e_value: f64,
+    /// The number of hits that were merged.
+    pub n_fragments: usize,
 }
 
 
 /// A hit within the [PyPfamFile] struct.
+#[derive(Debug, Clone)]
 pub struct PyPfamHit {
     /// The gene identifier of the target sequence.
     pub seq_id: String,
@@ -111,6 +185,7 @@ pub struct PyPfamHit {
     pub e_value: f64,
     /// The significance value is true if the bit score for a hit is greater than or equal to the curated gathering threshold for the matching family, false otherwise.
     /// Pfam-B hits are always assigned a significance value of "NA", since Pfam-B families do not have curated thresholds and the value is therefore meaningless.
+    /// Any value other than "1" or "NA" is parsed as false.
     pub significance: Option<bool>,
     /// Overlapping hits within clan member families (applies to Pfam-A families only)
     pub clan: String,
@@ -128,34 +203,50 @@ impl PyPfamHit {
     /// let hit = PyPfamHit::from_string(input_string).unwrap();
     /// ```
     pub fn from_string(string: &str) -> ProleResult<Self> {
-        let hits = RE_PFAM_LINE.captures(string)
-            .ok_or_else(|| ProleError::Exit(format!("Error parsing: {}", string)))?;
-        let significance: Option<bool> = if &hits[14] == "NA" {
-            None
-        } else {
-            Some(&hits[14] == "1")
+        let hits = string.split_whitespace().collect::<Vec<&str>>();
+        if hits.len() != PFAM_COLUMNS {
+            return Err(ProleError::Exit(format!("Expected {} columns, got {}: {}", PFAM_COLUMNS, hits.len(), string)));
+        }
+        let significance: Option<bool> = match hits[13] {
+            "NA" => None,
+            value => Some(value == "1"),
         };
         Ok(Self {
-            seq_id: hits[1].to_string(),
-            align_start: hits[2].parse().map_err(ProleError::ParseIntError)?,
-            align_end: hits[3].parse().map_err(ProleError::ParseIntError)?,
-            envelope_start: hits[4].parse().map_err(ProleError::ParseIntError)?,
-            envelope_end: hits[5].parse().map_err(ProleError::ParseIntError)?,
-            hmm_acc: hits[6].to_string(),
-            hmm_name: hits[7].to_string(),
-            hmm_type: hits[8].to_string(),
-            hmm_start: hits[9].parse().map_err(ProleError::ParseIntError)?,
-            hmm_end: hits[10].parse().map_err(ProleError::ParseIntError)?,
-            hmm_length: hits[11].parse().map_err(ProleError::ParseIntError)?,
-            bit_score: hits[12].parse().map_err(ProleError::ParseFloatError)?,
-            e_value: hits[13].parse().map_err(ProleError::ParseFloatError)?,
+            seq_id: hits[0].to_string(),
+            align_start: hits[1].parse().map_err(ProleError::ParseIntError)?,
+            align_end: hits[2].parse().map_err(ProleError::ParseIntError)?,
+            envelope_start: hits[3].parse().map_err(ProleError::ParseIntError)?,
+            envelope_end: hits[4].parse().map_err(ProleError::ParseIntError)?,
+            hmm_acc: hits[5].to_string(),
+            hmm_name: hits[6].to_string(),
+            hmm_type: hits[7].to_string(),
+            hmm_start: hits[8].parse().map_err(ProleError::ParseIntError)?,
+            hmm_end: hits[9].parse().map_err(ProleError::ParseIntError)?,
+            hmm_length: hits[10].parse().map_err(ProleError::ParseIntError)?,
+            bit_score: hits[11].parse().map_err(ProleError::ParseFloatError)?,
+            e_value: hits[12].parse().map_err(ProleError::ParseFloatError)?,
             significance,
-            clan: hits[15].to_string(),
+            clan: hits[14].to_string(),
         })
     }
 }
 
 
+impl std::fmt::Display for PyPfamHit {
+    /// A one-line summary of the hit, e.g. `CAKWUX010000001.1_1 PF02896.19 evalue=5.5e-76 score=252.7`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {} evalue={:e} score={}", self.seq_id, self.hmm_acc, self.e_value, self.bit_score)
+    }
+}
+
+
+impl FromLine for PyPfamHit {
+    fn from_line(line: &str) -> ProleResult<Self> {
+        Self::from_string(line)
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -167,6 +258,8 @@ mod tests {
     use super::*;
 
     #[test]
+    // The empty line is written explicitly, as skipping it is part of the test
+    #[allow(clippy::writeln_empty_string)]
     fn test_from_path_valid_file() {
         let mut file = NamedTempFile::new().unwrap();
         writeln!(file, "#to be ignored").unwrap();
@@ -209,6 +302,31 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_merge_adjacent() {
+        let content = [
+            "gene_1 1 100 1 100 PF00001.1 A Domain 1 100 200 50.0 1e-10 1 No_clan",
+            "gene_1 300 350 300 350 PF00001.1 A Domain 150 200 200 20.0 1e-3 1 No_clan",
+            "gene_1 104 200 104 200 PF00001.1 A Domain 101 200 200 60.0 1e-12 1 No_clan",
+            "gene_1 150 250 150 250 PF00002.1 B Domain 1 100 100 70.0 1e-15 1 No_clan",
+            "gene_2 107 200 107 200 PF00001.1 A Domain 101 200 200 60.0 1e-12 1 No_clan",
+            "gene_2 1 100 1 100 PF00001.1 A Domain 1 100 200 50.0 1e-10 1 No_clan",
+        ].join("\n");
+        let merged = PyPfamFile::from_reader(content.as_bytes()).unwrap().merge_adjacent(5);
+        let summary: Vec<(&str, &str, u32, u32, usize)> = merged.iter()
+            .map(|x| (x.seq_id.as_str(), x.hmm_acc.as_str(), x.align_start, x.align_end, x.n_fragments))
+            .collect();
+        assert_eq!(summary, vec![
+            ("gene_1", "PF00001.1", 1, 200, 2),
+            ("gene_1", "PF00002.1", 150, 250, 1),
+            ("gene_1", "PF00001.1", 300, 350, 1),
+            ("gene_2", "PF00001.1", 1, 100, 1),
+            ("gene_2", "PF00001.1", 107, 200, 1),
+        ]);
+        assert_eq!(merged[0].bit_score, 60.0);
+        assert_eq!(merged[0].e_value, 1e-12);
+    }
+
     #[test]
     fn test_from_string_valid() {
         let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151 ";
@@ -232,6 +350,13 @@ mod tests {
         assert_eq!(hit.clan, "CL0151");
     }
 
+    #[test]
+    fn test_display() {
+        let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151 ";
+        let hit = PyPfamHit::from_string(string).unwrap();
+        assert_eq!(hit.to_string(), "CAKWUX010000001.1_1 PF02896.19 evalue=5.5e-76 score=252.7");
+    }
+
     #[test]
     fn test_from_string_invalid_format() {
         let string = "CAKWUX010000001.1_1       1    263      1    ";
@@ -245,4 +370,23 @@ mod tests {
         let result = PyPfamHit::from_string(string);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_from_string_column_count() {
+        let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151 extra";
+        match PyPfamHit::from_string(string) {
+            Err(ProleError::Exit(msg)) => assert!(msg.starts_with("Expected 15 columns, got 16: CAKWUX010000001.1_1")),
+            _ => panic!("Expected a column count error"),
+        }
+    }
+
+    #[test]
+    fn test_from_string_significance() {
+        let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   NA CL0151";
+        assert_eq!(PyPfamHit::from_string(string).unwrap().significance, None);
+        let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   0 CL0151";
+        assert_eq!(PyPfamHit::from_string(string).unwrap().significance, Some(false));
+        let string = "CAKWUX010000001.1_1       1    263      2    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   x CL0151";
+        assert_eq!(PyPfamHit::from_string(string).unwrap().significance, Some(false));
+    }
 }
@@ -1,2 +1,3 @@
 pub mod red_dict;
-pub mod red;
\ No newline at end of file
+pub mod red;
+pub mod red_file;
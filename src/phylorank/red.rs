@@ -1,14 +1,141 @@
 use derive_more::Sub;
 
+use crate::error::{ProleError, ProleResult};
+
 /// A Relative Evolutionary Divergence ([RED]) score.
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Sub)]
 pub struct RED(pub f64);
 
 
 impl RED {
+    /// Create a [RED] score, returning an error if the value is not a finite number within `[0, 1]`.
+    ///
+    /// ```
+    /// use prole::phylorank::red::RED;
+    ///
+    /// assert_eq!(RED::new(0.5).unwrap(), RED(0.5));
+    /// assert!(RED::new(1.5).is_err());
+    /// ```
+    pub fn new(value: f64) -> ProleResult<Self> {
+        if !value.is_finite() || !(0.0..=1.0).contains(&value) {
+            return Err(ProleError::Exit(format!("RED value must be within [0, 1]: {}", value)));
+        }
+        Ok(RED(value))
+    }
+
+    /// Linearly map this [RED] score from the interval `from` onto the interval `to`, e.g. to
+    /// align scores computed on different trees using shared reference points.
+    ///
+    /// The result is not clamped, therefore values outside `from` are extrapolated. An error is
+    /// returned if `from` has zero width, as the mapping is undefined.
+    ///
+    /// ```
+    /// use prole::phylorank::red::RED;
+    ///
+    /// let red = RED(0.5).rescale((RED(0.4), RED(0.6)), (RED(0.0), RED(1.0))).unwrap();
+    /// assert!((red.0 - 0.5).abs() < 1e-12);
+    /// ```
+    pub fn rescale(&self, from: (RED, RED), to: (RED, RED)) -> ProleResult<RED> {
+        let from_width = from.1.0 - from.0.0;
+        if from_width == 0.0 || !from_width.is_finite() {
+            return Err(ProleError::Exit(format!("Unable to rescale from a zero-width interval: [{}, {}]", from.0.0, from.1.0)));
+        }
+        let fraction = (self.0 - from.0.0) / from_width;
+        Ok(RED(to.0.0 + fraction * (to.1.0 - to.0.0)))
+    }
+
     /// Return the absolute value of the [RED] score.
     pub fn abs(&self) -> Self {
         RED(self.0.abs())
     }
 }
 
+
+/// Returns the non-NaN values from a slice of [RED] scores.
+fn finite_values(reds: &[RED]) -> impl Iterator<Item=f64> + '_ {
+    reds.iter().map(|red| red.0).filter(|value| !value.is_nan())
+}
+
+/// Returns the mean of the [RED] scores, ignoring NaN values.
+/// `None` is returned if there are no values.
+///
+/// ```
+/// use prole::phylorank::red::{mean, RED};
+///
+/// assert_eq!(mean(&[RED(0.2), RED(0.6), RED(f64::NAN)]), Some(RED(0.4)));
+/// assert_eq!(mean(&[]), None);
+/// ```
+pub fn mean(reds: &[RED]) -> Option<RED> {
+    let (sum, count) = finite_values(reds).fold((0.0, 0usize), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 {
+        return None;
+    }
+    Some(RED(sum / count as f64))
+}
+
+/// Returns the minimum of the [RED] scores, ignoring NaN values.
+/// `None` is returned if there are no values.
+pub fn min(reds: &[RED]) -> Option<RED> {
+    finite_values(reds).reduce(f64::min).map(RED)
+}
+
+/// Returns the maximum of the [RED] scores, ignoring NaN values.
+/// `None` is returned if there are no values.
+pub fn max(reds: &[RED]) -> Option<RED> {
+    finite_values(reds).reduce(f64::max).map(RED)
+}
+
+/// Returns the population standard deviation of the [RED] scores, ignoring NaN values.
+/// `None` is returned if there are no values.
+pub fn std_dev(reds: &[RED]) -> Option<f64> {
+    let mean = mean(reds)?.0;
+    let (sum_sq, count) = finite_values(reds)
+        .fold((0.0, 0usize), |(sum_sq, count), value| (sum_sq + (value - mean).powi(2), count + 1));
+    Some((sum_sq / count as f64).sqrt())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(RED::new(0.0).unwrap(), RED(0.0));
+        assert_eq!(RED::new(0.5).unwrap(), RED(0.5));
+        assert_eq!(RED::new(1.0).unwrap(), RED(1.0));
+        assert!(RED::new(1.5).is_err());
+        assert!(RED::new(-0.1).is_err());
+        assert!(RED::new(f64::NAN).is_err());
+        assert!(RED::new(f64::INFINITY).is_err());
+        assert!(RED::new(f64::NEG_INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_rescale() {
+        let from = (RED(0.2), RED(0.6));
+        let to = (RED(0.0), RED(0.8));
+        assert!((RED(0.2).rescale(from, to).unwrap().0 - 0.0).abs() < 1e-12);
+        assert!((RED(0.6).rescale(from, to).unwrap().0 - 0.8).abs() < 1e-12);
+        assert!((RED(0.4).rescale(from, to).unwrap().0 - 0.4).abs() < 1e-12);
+        assert!((RED(0.7).rescale(from, to).unwrap().0 - 1.0).abs() < 1e-12);
+        // Reversed intervals are allowed
+        assert!((RED(0.5).rescale((RED(1.0), RED(0.0)), to).unwrap().0 - 0.4).abs() < 1e-12);
+        assert!(RED(0.5).rescale((RED(0.3), RED(0.3)), to).is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let reds = [RED(0.2), RED(0.4), RED(f64::NAN), RED(0.6), RED(0.8)];
+        assert!((mean(&reds).unwrap().0 - 0.5).abs() < 1e-12);
+        assert_eq!(min(&reds), Some(RED(0.2)));
+        assert_eq!(max(&reds), Some(RED(0.8)));
+        assert!((std_dev(&reds).unwrap() - 0.05f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(mean(&[]), None);
+        assert_eq!(min(&[RED(f64::NAN)]), None);
+        assert_eq!(max(&[]), None);
+        assert_eq!(std_dev(&[RED(f64::NAN)]), None);
+        assert_eq!(std_dev(&[RED(0.3)]), Some(0.0));
+    }
+}
@@ -13,6 +13,7 @@ lazy_static! {
 }
 
 /// The [RED] dictionary output by PhyloRank.
+#[derive(Debug, Copy, Clone)]
 pub struct PhyloRankRedDict {
     pub phylum: RED,
     pub class: RED,
@@ -34,6 +35,39 @@ impl PhyloRankRedDict {
         }
     }
 
+    /// Returns the `[lower, upper)` [RED] interval for each [TaxonomyRank] (domain to species),
+    /// where the bounds are the midpoints between the [RED] values of neighbouring ranks.
+    ///
+    /// The domain and species are taken to have a [RED] of 0 and 1 respectively, therefore the
+    /// domain interval starts at 0 and the species interval ends at 1 (inclusive).
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    /// use prole::phylorank::red::RED;
+    /// use prole::phylorank::red_dict::PhyloRankRedDict;
+    ///
+    /// let red_dict = PhyloRankRedDict { phylum: RED(0.2), class: RED(0.4), order: RED(0.6), family: RED(0.7), genus: RED(0.9) };
+    /// let (rank, lower, upper) = red_dict.rank_boundaries()[2];
+    /// assert_eq!(rank, TaxonomyRank::Class);
+    /// assert!((lower - 0.3).abs() < 1e-9 && (upper - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn rank_boundaries(&self) -> Vec<(TaxonomyRank, f64, f64)> {
+        let values: Vec<f64> = TaxonomyRank::all()
+            .map(|rank| match rank {
+                TaxonomyRank::Domain => 0.0,
+                TaxonomyRank::Species => 1.0,
+                _ => self.get(&rank).map_or(0.0, |red| red.0),
+            })
+            .collect();
+        TaxonomyRank::all().enumerate()
+            .map(|(i, rank)| {
+                let lower = if i == 0 { 0.0 } else { (values[i - 1] + values[i]) / 2.0 };
+                let upper = if i == values.len() - 1 { 1.0 } else { (values[i] + values[i + 1]) / 2.0 };
+                (rank, lower, upper)
+            })
+            .collect()
+    }
+
     /// Load the [PhyloRankRedDict] from the [Path].
     pub fn load(path: &Path) -> ProleResult<Self> {
         let file = std::fs::File::open(path).map_err(ProleError::IoError)?;
@@ -44,11 +78,11 @@ impl PhyloRankRedDict {
         let hits = RE_DICT.captures(&contents).ok_or(ProleError::Exit("No hits found".to_string()))?;
 
         Ok(Self {
-            phylum: RED(hits[1].parse().map_err(ProleError::ParseFloatError)?),
-            class: RED(hits[2].parse().map_err(ProleError::ParseFloatError)?),
-            order: RED(hits[3].parse().map_err(ProleError::ParseFloatError)?),
-            family: RED(hits[4].parse().map_err(ProleError::ParseFloatError)?),
-            genus: RED(hits[5].parse().map_err(ProleError::ParseFloatError)?),
+            phylum: RED::new(hits[1].parse().map_err(ProleError::ParseFloatError)?)?,
+            class: RED::new(hits[2].parse().map_err(ProleError::ParseFloatError)?)?,
+            order: RED::new(hits[3].parse().map_err(ProleError::ParseFloatError)?)?,
+            family: RED::new(hits[4].parse().map_err(ProleError::ParseFloatError)?)?,
+            genus: RED::new(hits[5].parse().map_err(ProleError::ParseFloatError)?)?,
         })
     }
 }
@@ -81,6 +115,38 @@ mod tests {
         assert_eq!(red_dict.get(&TaxonomyRank::Genus).unwrap(), RED(0.89));
     }
 
+    #[test]
+    fn test_rank_boundaries() {
+        let red_dict = PhyloRankRedDict {
+            phylum: RED(0.2),
+            class: RED(0.4),
+            order: RED(0.6),
+            family: RED(0.7),
+            genus: RED(0.9),
+        };
+        let expected = [
+            (TaxonomyRank::Domain, 0.0, 0.1),
+            (TaxonomyRank::Phylum, 0.1, 0.3),
+            (TaxonomyRank::Class, 0.3, 0.5),
+            (TaxonomyRank::Order, 0.5, 0.65),
+            (TaxonomyRank::Family, 0.65, 0.8),
+            (TaxonomyRank::Genus, 0.8, 0.95),
+            (TaxonomyRank::Species, 0.95, 1.0),
+        ];
+        let boundaries = red_dict.rank_boundaries();
+        assert_eq!(boundaries.len(), expected.len());
+        for ((rank, lower, upper), (exp_rank, exp_lower, exp_upper)) in boundaries.iter().zip(expected.iter()) {
+            assert_eq!(rank, exp_rank);
+            assert!((lower - exp_lower).abs() < 1e-9);
+            assert!((upper - exp_upper).abs() < 1e-9);
+        }
+
+        // The intervals are contiguous
+        for pair in boundaries.windows(2) {
+            assert_eq!(pair[0].2, pair[1].1);
+        }
+    }
+
     #[test]
     fn test_load() {
         // Create a temporary file with sample RED data
@@ -94,4 +160,11 @@ mod tests {
         assert_eq!(red_dict.family.0, 0.70);
         assert_eq!(red_dict.genus.0, 0.89);
     }
+
+    #[test]
+    fn test_load_out_of_range() {
+        let mut tmp_file = NamedTempFile::new().unwrap();
+        writeln!(tmp_file, r#"{{"phylum":0.21,"class":0.35,"order":0.51,"family":0.70,"genus":1.5}}"#).unwrap();
+        assert!(PhyloRankRedDict::load(tmp_file.path()).is_err());
+    }
 }
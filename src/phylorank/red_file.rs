@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+
+use crate::error::{ProleError, ProleResult};
+use crate::gtdb::taxon::Taxon;
+use crate::gtdb::taxonomy_rank::TaxonomyRank;
+use crate::phylorank::red::RED;
+use crate::util::parse::read_lines;
+
+/// Parse the rank column, which may either be the prefix (e.g. `g`) or the name (e.g. `Genus`).
+fn parse_rank(string: &str) -> ProleResult<TaxonomyRank> {
+    let string = string.trim().trim_end_matches("__").to_ascii_lowercase();
+    TaxonomyRank::from_prefix(&string)
+        .or_else(|| TaxonomyRank::all().find(|rank| format!("{:?}", rank).to_ascii_lowercase() == string))
+        .ok_or_else(|| ProleError::Exit(format!("Unrecognised rank: {}", string)))
+}
+
+/// The [RED] assigned to each named [Taxon] by PhyloRank, as opposed to the per-rank medians
+/// in the [crate::phylorank::red_dict::PhyloRankRedDict].
+///
+/// The file is tab separated with the taxon, rank, and RED value as the first three columns
+/// (any further columns are ignored). The rank may be given as a prefix or name, and must match
+/// the prefix of the taxon. An optional header (starting with `Taxon` or `Taxa`) is skipped.
+///
+/// ```text
+/// Taxon    Rank    RED
+/// g__Thermococcus_A    genus    0.912
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedFile(pub HashMap<Taxon, RED>);
+
+impl RedFile {
+    /// Read the content from a [BufReader] and parse it into a [RedFile].
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use prole::gtdb::taxon::Taxon;
+    /// use prole::phylorank::red::RED;
+    /// use prole::phylorank::red_file::RedFile;
+    ///
+    /// let content = "Taxon\tRank\tRED\ng__g1\tgenus\t0.9\n";
+    /// let file = RedFile::from_bufreader(BufReader::new(content.as_bytes())).unwrap();
+    /// assert_eq!(file.get(&Taxon("g__g1".to_string())), Some(RED(0.9)));
+    /// ```
+    pub fn from_bufreader<T: std::io::Read>(buf: BufReader<T>) -> ProleResult<Self> {
+        let mut out = HashMap::new();
+        for line in read_lines(buf) {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Taxon\t") || line.starts_with("Taxa\t") {
+                continue;
+            }
+            let split: Vec<&str> = line.split('\t').collect();
+            if split.len() < 3 {
+                return Err(ProleError::Exit(format!("Expected at least 3 columns: {}", line)));
+            }
+            let taxon = Taxon(split[0].trim().to_string());
+            let rank = parse_rank(split[1])?;
+            if taxon.rank() != Some(rank) {
+                return Err(ProleError::Exit(format!("Taxon does not match rank {:?}: {}", rank, taxon.0)));
+            }
+            let red = RED::new(split[2].trim().parse().map_err(ProleError::ParseFloatError)?)?;
+            if out.insert(taxon, red).is_some() {
+                return Err(ProleError::Exit(format!("Duplicate taxon found in RED file: {}", split[0])));
+            }
+        }
+        Ok(Self(out))
+    }
+
+    /// Read the content from any [std::io::Read] and parse it into a [RedFile], the reader is
+    /// buffered internally.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> ProleResult<Self> {
+        Self::from_bufreader(BufReader::new(reader))
+    }
+
+    /// Read the content from a [Path] and parse it into a [RedFile].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::phylorank::red_file::RedFile;
+    ///
+    /// let file = RedFile::from_path(Path::new("/path/to/red.tsv")).unwrap();
+    /// ```
+    pub fn from_path(path: &Path) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(file);
+        Self::from_bufreader(reader)
+    }
+
+    /// Read the content from a gz compressed file at [Path] and parse it into a [RedFile].
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use prole::phylorank::red_file::RedFile;
+    ///
+    /// let file = RedFile::from_path_gz(Path::new("/path/to/red.tsv.gz")).unwrap();
+    /// ```
+    pub fn from_path_gz(path: &Path) -> ProleResult<Self> {
+        let file = File::open(path).map_err(ProleError::IoError)?;
+        let reader = BufReader::new(GzDecoder::new(file));
+        Self::from_bufreader(reader)
+    }
+
+    /// Returns the [RED] of `taxon`.
+    pub fn get(&self, taxon: &Taxon) -> Option<RED> {
+        self.0.get(taxon).copied()
+    }
+
+    /// Returns the [Taxon]s (sorted by name) and their [RED] at the specified [TaxonomyRank].
+    ///
+    /// ```
+    /// use prole::gtdb::taxonomy_rank::TaxonomyRank;
+    /// use prole::phylorank::red_file::RedFile;
+    ///
+    /// let file = RedFile::from_reader("p__p1\tphylum\t0.2\ng__g2\tg\t0.9\ng__g1\tgenus\t0.8\n".as_bytes()).unwrap();
+    /// let genera = file.by_rank(TaxonomyRank::Genus);
+    /// assert_eq!(genera.iter().map(|(taxon, _)| taxon.0.as_str()).collect::<Vec<_>>(), vec!["g__g1", "g__g2"]);
+    /// ```
+    pub fn by_rank(&self, rank: TaxonomyRank) -> Vec<(&Taxon, RED)> {
+        let mut out: Vec<(&Taxon, RED)> = self.0.iter()
+            .filter(|(taxon, _)| taxon.rank() == Some(rank))
+            .map(|(taxon, &red)| (taxon, red))
+            .collect();
+        out.sort_by(|a, b| a.0.0.cmp(&b.0.0));
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    const CONTENT: &str = "Taxon\tRank\tRED\nd__d1\tdomain\t0\np__p1\tPhylum\t0.25\nc__c1\tc\t0.4\tignored\ng__g1\tgenus\t0.91\n";
+
+    #[test]
+    fn test_from_path_gz() {
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(CONTENT.as_bytes()).unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&e.finish().unwrap()).unwrap();
+
+        let result = RedFile::from_path_gz(&file.into_temp_path()).unwrap();
+        assert_eq!(result.0.len(), 4);
+        assert_eq!(result.get(&Taxon("p__p1".to_string())), Some(RED(0.25)));
+        assert_eq!(result.get(&Taxon("c__c1".to_string())), Some(RED(0.4)));
+        assert_eq!(result.get(&Taxon("g__g2".to_string())), None);
+        assert_eq!(result.by_rank(TaxonomyRank::Domain), vec![(&Taxon("d__d1".to_string()), RED(0.0))]);
+        assert!(result.by_rank(TaxonomyRank::Species).is_empty());
+    }
+
+    #[test]
+    fn test_from_reader_invalid() {
+        for content in [
+            "g__g1\tgenus\n",
+            "g__g1\tfamily\t0.9\n",
+            "g__g1\tgenera\t0.9\n",
+            "g__g1\tgenus\t1.9\n",
+            "g__g1\tgenus\tx\n",
+            "g__g1\tgenus\t0.9\ng__g1\tgenus\t0.8\n",
+        ] {
+            assert!(RedFile::from_reader(content.as_bytes()).is_err(), "{}", content);
+        }
+    }
+}
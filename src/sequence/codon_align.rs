@@ -0,0 +1,53 @@
+use crate::error::{ProleError, ProleResult};
+
+/// Project an unaligned nucleotide CDS onto its aligned protein sequence (i.e. pal2nal), giving
+/// a codon alignment. Each residue is replaced by its codon, and each gap (`-` or `.`) by `---`.
+///
+/// An error is returned if the ungapped protein length × 3 is not equal to the nucleotide
+/// length (e.g. the CDS includes a stop codon that is not in the protein).
+///
+/// ```
+/// use prole::sequence::codon_align::project;
+///
+/// assert_eq!(project("M-K", "ATGAAA").unwrap(), "ATG---AAA");
+/// assert!(project("M-K", "ATGAAATAA").is_err());
+/// ```
+pub fn project(protein_aln: &str, nucleotide: &str) -> ProleResult<String> {
+    let is_gap = |c: char| c == '-' || c == '.';
+    let n_residues = protein_aln.chars().filter(|&c| !is_gap(c)).count();
+    if !nucleotide.is_ascii() {
+        return Err(ProleError::Exit("Nucleotide sequence contains non-ASCII characters".to_string()));
+    }
+    if n_residues * 3 != nucleotide.len() {
+        return Err(ProleError::Exit(format!("Protein has {} residues ({} bases), but the nucleotide sequence has {} bases",
+                                            n_residues, n_residues * 3, nucleotide.len())));
+    }
+
+    let mut out = String::with_capacity(protein_aln.chars().count() * 3);
+    let mut codons = nucleotide.as_bytes().chunks(3);
+    for c in protein_aln.chars() {
+        if is_gap(c) {
+            out.push_str("---");
+        } else if let Some(codon) = codons.next() {
+            out.push_str(std::str::from_utf8(codon).unwrap_or_default());
+        }
+    }
+    Ok(out)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        assert_eq!(project("MK-W.", "ATGAAGTGG").unwrap(), "ATGAAG---TGG---");
+        assert_eq!(project("--M", "ATG").unwrap(), "------ATG");
+        assert_eq!(project("", "").unwrap(), "");
+        assert_eq!(project("---", "").unwrap(), "---------");
+        assert!(project("MK", "ATGAA").is_err());
+        assert!(project("MK", "ATGAAATAA").is_err());
+        assert!(project("M", "ATé").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+/// Case-insensitive counts of each base type within a sequence.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BaseCounts {
+    pub a: usize,
+    pub c: usize,
+    pub g: usize,
+    pub t: usize,
+    /// Ambiguous `N` bases.
+    pub n: usize,
+    /// Any other character (e.g. IUPAC ambiguity codes).
+    pub other: usize,
+    /// Alignment gaps (`-` or `.`).
+    pub gap: usize,
+}
+
+impl BaseCounts {
+    /// Count the bases in a sequence.
+    ///
+    /// ```
+    /// use prole::sequence::composition::BaseCounts;
+    ///
+    /// let counts = BaseCounts::from_seq("AcgTNn-R");
+    /// assert_eq!((counts.a, counts.c, counts.g, counts.t), (1, 1, 1, 1));
+    /// assert_eq!((counts.n, counts.gap, counts.other), (2, 1, 1));
+    /// ```
+    pub fn from_seq(seq: &str) -> Self {
+        let mut out = Self::default();
+        for base in seq.bytes() {
+            match base {
+                b'A' | b'a' => out.a += 1,
+                b'C' | b'c' => out.c += 1,
+                b'G' | b'g' => out.g += 1,
+                b'T' | b't' => out.t += 1,
+                b'N' | b'n' => out.n += 1,
+                b'-' | b'.' => out.gap += 1,
+                _ => out.other += 1,
+            }
+        }
+        out
+    }
+
+    /// Returns the total number of characters counted.
+    pub fn total(&self) -> usize {
+        self.a + self.c + self.g + self.t + self.n + self.other + self.gap
+    }
+}
+
+impl std::ops::AddAssign for BaseCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.a += other.a;
+        self.c += other.c;
+        self.g += other.g;
+        self.t += other.t;
+        self.n += other.n;
+        self.other += other.other;
+        self.gap += other.gap;
+    }
+}
+
+/// Compute the GC skew, `(G - C) / (G + C)`, over sliding windows of `window` bases that start
+/// every `step` bases. Bases are counted case-insensitively.
+///
+/// Only complete windows are returned, i.e. a trailing partial window is dropped, and no values
+/// are returned if the sequence is shorter than `window` (or either `window` or `step` is 0).
+/// A window without any G or C has a skew of `0.0`.
+///
+/// ```
+/// use prole::sequence::composition::gc_skew;
+///
+/// assert_eq!(gc_skew("GGGCAAAA", 4, 4), vec![0.5, 0.0]);
+/// ```
+pub fn gc_skew(seq: &str, window: usize, step: usize) -> Vec<f64> {
+    let seq = seq.as_bytes();
+    if window == 0 || step == 0 || seq.len() < window {
+        return vec![];
+    }
+
+    // Cumulative counts of G and C, offset by one so that cum[i] is the count before base i
+    let mut cum_g = Vec::with_capacity(seq.len() + 1);
+    let mut cum_c = Vec::with_capacity(seq.len() + 1);
+    let (mut g, mut c) = (0_i64, 0_i64);
+    cum_g.push(g);
+    cum_c.push(c);
+    for base in seq {
+        match base {
+            b'G' | b'g' => g += 1,
+            b'C' | b'c' => c += 1,
+            _ => {}
+        }
+        cum_g.push(g);
+        cum_c.push(c);
+    }
+
+    (0..=seq.len() - window)
+        .step_by(step)
+        .map(|start| {
+            let g = cum_g[start + window] - cum_g[start];
+            let c = cum_c[start + window] - cum_c[start];
+            if g + c == 0 { 0.0 } else { (g - c) as f64 / (g + c) as f64 }
+        })
+        .collect()
+}
+
+/// Compute the Shannon entropy (base 2) of a nucleotide or protein sequence from the observed
+/// residue frequencies. Residues are counted case-insensitively, and gaps (`-` or `.`) are
+/// ignored. A sequence without any residues has an entropy of `0.0`.
+///
+/// ```
+/// use prole::sequence::composition::shannon_entropy;
+///
+/// assert_eq!(shannon_entropy("AAAA"), 0.0);
+/// assert_eq!(shannon_entropy("ACGT"), 2.0);
+/// ```
+pub fn shannon_entropy(seq: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    let mut total = 0;
+    for residue in seq.bytes() {
+        if residue != b'-' && residue != b'.' {
+            counts[residue.to_ascii_uppercase() as usize] += 1;
+            total += 1;
+        }
+    }
+    counts.iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>()
+        .max(0.0)
+}
+
+/// Returns true if the [shannon_entropy] of the sequence is below `threshold`, e.g. a
+/// homopolymer run. A sequence without any residues is considered low complexity for any
+/// positive `threshold`.
+///
+/// ```
+/// use prole::sequence::composition::is_low_complexity;
+///
+/// assert!(is_low_complexity("AAAAAAAAAT", 1.0));
+/// assert!(!is_low_complexity("ACGTACGTAC", 1.0));
+/// ```
+pub fn is_low_complexity(seq: &str, threshold: f64) -> bool {
+    shannon_entropy(seq) < threshold
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_counts() {
+        let mut counts = BaseCounts::from_seq("AAcgTTTnN--.RY");
+        assert_eq!(counts, BaseCounts { a: 2, c: 1, g: 1, t: 3, n: 2, other: 2, gap: 3 });
+        assert_eq!(counts.total(), 14);
+
+        counts += BaseCounts::from_seq("ga");
+        assert_eq!(counts, BaseCounts { a: 3, c: 1, g: 2, t: 3, n: 2, other: 2, gap: 3 });
+        assert_eq!(BaseCounts::from_seq(""), BaseCounts::default());
+    }
+
+    #[test]
+    fn test_gc_skew() {
+        assert_eq!(gc_skew("GGGCAAAA", 4, 4), vec![0.5, 0.0]);
+        assert_eq!(gc_skew("ggccGGGG", 4, 2), vec![0.0, 0.0, 1.0]);
+        assert_eq!(gc_skew("CCCCAT", 4, 1), vec![-1.0, -1.0, -1.0]);
+
+        // The trailing partial window is dropped
+        assert_eq!(gc_skew("GGGGC", 2, 2), vec![1.0, 1.0]);
+
+        assert!(gc_skew("GGG", 4, 1).is_empty());
+        assert!(gc_skew("GGGG", 0, 1).is_empty());
+        assert!(gc_skew("GGGG", 1, 0).is_empty());
+        assert!(gc_skew("", 1, 1).is_empty());
+    }
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy("AAAAAAAA"), 0.0);
+        assert_eq!(shannon_entropy("ACGTACGT"), 2.0);
+        assert_eq!(shannon_entropy("acGT--.."), 2.0);
+        assert_eq!(shannon_entropy("AATT"), 1.0);
+        assert_eq!(shannon_entropy(""), 0.0);
+        assert_eq!(shannon_entropy("---"), 0.0);
+        assert!((shannon_entropy("AAAC") - 0.8112781244591328).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_is_low_complexity() {
+        assert!(is_low_complexity("AAAAAAAA", 0.5));
+        assert!(!is_low_complexity("ACGTACGT", 0.5));
+        assert!(is_low_complexity("ACGTACGT", 2.5));
+        assert!(is_low_complexity("", 0.5));
+    }
+}
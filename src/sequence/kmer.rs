@@ -0,0 +1,175 @@
+use crate::error::{ProleError, ProleResult};
+
+/// The maximum k-mer length supported, as a lookup with an entry for each of the `4^k` k-mers
+/// is allocated.
+pub const MAX_K: usize = 12;
+
+/// Encode a nucleotide as a 2-bit value (A=0, C=1, G=2, T=3), returning [None] for any other character.
+fn encode_base(base: u8) -> Option<usize> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// Return the 2-bit encoding of the reverse complement of an encoded k-mer.
+fn revcomp_code(code: usize, k: usize) -> usize {
+    let mut rc = 0;
+    for i in 0..k {
+        rc = (rc << 2) | (3 - ((code >> (2 * i)) & 3));
+    }
+    rc
+}
+
+/// Returns an error if `k` is not between 1 and [MAX_K] (inclusive).
+fn validate_k(k: usize) -> ProleResult<()> {
+    if k == 0 || k > MAX_K {
+        return Err(ProleError::Exit(format!("Invalid k-mer length (expected 1 to {}): {}", MAX_K, k)));
+    }
+    Ok(())
+}
+
+/// Return the canonical k-mers (i.e. the lexicographically smaller of a k-mer and its reverse
+/// complement) of length `k`, in lexicographic order. This is the order used by
+/// [kmer_frequency_vector]. An error is returned if `k` is not between 1 and [MAX_K].
+///
+/// ```
+/// use prole::sequence::kmer::canonical_kmers;
+///
+/// assert_eq!(canonical_kmers(1).unwrap(), vec!["A", "C"]);
+/// assert_eq!(canonical_kmers(4).unwrap().len(), 136);
+/// assert!(canonical_kmers(32).is_err());
+/// ```
+pub fn canonical_kmers(k: usize) -> ProleResult<Vec<String>> {
+    validate_k(k)?;
+    let n_kmers = 1 << (2 * k);
+    Ok((0..n_kmers)
+        .filter(|code| *code <= revcomp_code(*code, k))
+        .map(|code| {
+            (0..k).rev().map(|i| ['A', 'C', 'G', 'T'][(code >> (2 * i)) & 3]).collect()
+        })
+        .collect())
+}
+
+/// A lookup from every k-mer of length `k` to the index of its canonical k-mer, for counting
+/// the canonical k-mers of many sequences without rebuilding the lookup each time.
+#[derive(Debug, Clone)]
+pub struct KmerIndex {
+    k: usize,
+    index: Vec<usize>,
+    n_canonical: usize,
+}
+
+impl KmerIndex {
+    /// Create the [KmerIndex] for k-mers of length `k`, an error is returned if `k` is not
+    /// between 1 and [MAX_K].
+    pub fn new(k: usize) -> ProleResult<Self> {
+        validate_k(k)?;
+        let n_kmers = 1 << (2 * k);
+        let mut index = vec![0; n_kmers];
+        let mut n_canonical = 0;
+        for code in 0..n_kmers {
+            let rc = revcomp_code(code, k);
+            if code <= rc {
+                index[code] = n_canonical;
+                n_canonical += 1;
+            } else {
+                index[code] = index[rc];
+            }
+        }
+        Ok(Self { k, index, n_canonical })
+    }
+
+    /// Count the canonical k-mers within `seq` and return their relative frequencies, see
+    /// [kmer_frequency_vector].
+    ///
+    /// ```
+    /// use prole::sequence::kmer::KmerIndex;
+    ///
+    /// let index = KmerIndex::new(3).unwrap();
+    /// assert_eq!(index.frequency_vector(b"AAATTT")[0], 0.5);
+    /// assert_eq!(index.frequency_vector(b"CCC").len(), 32);
+    /// ```
+    pub fn frequency_vector(&self, seq: &[u8]) -> Vec<f64> {
+        let mut counts = vec![0_u64; self.n_canonical];
+        let mask = (1 << (2 * self.k)) - 1;
+        let mut code = 0;
+        let mut valid = 0;
+        for base in seq {
+            match encode_base(*base) {
+                Some(enc) => {
+                    code = ((code << 2) | enc) & mask;
+                    valid += 1;
+                    if valid >= self.k {
+                        counts[self.index[code]] += 1;
+                    }
+                }
+                None => valid = 0,
+            }
+        }
+
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return vec![0.0; self.n_canonical];
+        }
+        counts.into_iter().map(|x| x as f64 / total as f64).collect()
+    }
+}
+
+/// Count the canonical k-mers of length `k` within `seq` and return their relative frequencies,
+/// ordered as per [canonical_kmers]. K-mers containing characters other than `ACGT`
+/// (case-insensitive) are skipped. If no k-mers were counted, all frequencies are zero.
+///
+/// An error is returned if `k` is not between 1 and [MAX_K]. Use a [KmerIndex] when counting
+/// many sequences, as the lookup is built on each call.
+///
+/// ```
+/// use prole::sequence::kmer::kmer_frequency_vector;
+///
+/// // AAA and TTT are the same canonical k-mer
+/// let freq = kmer_frequency_vector(b"AAATTT", 3).unwrap();
+/// assert_eq!(freq.len(), 32);
+/// assert_eq!(freq[0], 0.5);
+/// assert!(kmer_frequency_vector(b"AAATTT", 0).is_err());
+/// ```
+pub fn kmer_frequency_vector(seq: &[u8], k: usize) -> ProleResult<Vec<f64>> {
+    Ok(KmerIndex::new(k)?.frequency_vector(seq))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_kmers() {
+        assert_eq!(canonical_kmers(2).unwrap(), vec!["AA", "AC", "AG", "AT", "CA", "CC", "CG", "GA", "GC", "TA"]);
+        assert_eq!(canonical_kmers(4).unwrap().len(), 136);
+        assert_eq!(canonical_kmers(4).unwrap()[0], "AAAA");
+    }
+
+    #[test]
+    fn test_kmer_frequency_vector() {
+        let kmers = canonical_kmers(2).unwrap();
+        let freq = kmer_frequency_vector(b"ACgtNTT", 2).unwrap();
+        assert_eq!(freq.len(), kmers.len());
+
+        // AC, CG, GT (=AC), TT (=AA)
+        let idx = |kmer: &str| kmers.iter().position(|x| x == kmer).unwrap();
+        assert_eq!(freq[idx("AC")], 0.5);
+        assert_eq!(freq[idx("CG")], 0.25);
+        assert_eq!(freq[idx("AA")], 0.25);
+        assert_eq!(freq.iter().sum::<f64>(), 1.0);
+
+        assert!(kmer_frequency_vector(b"ACG", 4).unwrap().iter().all(|x| *x == 0.0));
+        assert!(kmer_frequency_vector(b"", 4).unwrap().iter().all(|x| *x == 0.0));
+
+        // The lookup for large k would overflow (k >= 32) or be too large to allocate
+        for k in [0, MAX_K + 1, 32, 64] {
+            assert!(kmer_frequency_vector(b"ACGT", k).is_err(), "{}", k);
+        }
+    }
+}
@@ -1,2 +1,7 @@
 pub mod tln_table;
-pub mod translate;
\ No newline at end of file
+pub mod translate;
+pub mod kmer;
+pub mod packed;
+pub mod composition;
+pub mod codon_align;
+pub mod tm;
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{ProleError, ProleResult};
+
+/// The nucleotide represented by each 2-bit value.
+const BASES: [u8; 4] = [b'A', b'C', b'G', b'T'];
+
+/// A nucleotide sequence stored using 2 bits per base (four bases per byte). Only the
+/// bases `ACGT` (case-insensitive) can be stored, and are always returned uppercase.
+///
+/// ```
+/// use std::str::FromStr;
+/// use prole::sequence::packed::PackedSeq;
+///
+/// let seq = PackedSeq::from_str("ACGTtg").unwrap();
+/// assert_eq!(seq.len(), 6);
+/// assert_eq!(seq.get(4), b'T');
+/// assert_eq!(seq.to_string(), "ACGTTG");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PackedSeq {
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl PackedSeq {
+    /// Return the number of bases in the sequence.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the sequence contains no bases.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the (uppercase) base at position `i` (0-based).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> u8 {
+        assert!(i < self.len, "index out of bounds: the len is {} but the index is {}", self.len, i);
+        BASES[((self.data[i / 4] >> (2 * (i % 4))) & 3) as usize]
+    }
+}
+
+impl FromStr for PackedSeq {
+    type Err = ProleError;
+
+    /// Pack a nucleotide sequence, returning an error if it contains any base other than `ACGT`.
+    fn from_str(seq: &str) -> ProleResult<Self> {
+        let mut data = vec![0_u8; (seq.len() + 3) / 4];
+        for (i, base) in seq.bytes().enumerate() {
+            let code = match base {
+                b'A' | b'a' => 0,
+                b'C' | b'c' => 1,
+                b'G' | b'g' => 2,
+                b'T' | b't' => 3,
+                _ => return Err(ProleError::Exit(format!("Unable to pack base at position {}: {}", i, base as char))),
+            };
+            data[i / 4] |= code << (2 * (i % 4));
+        }
+        Ok(Self { data, len: seq.len() })
+    }
+}
+
+impl fmt::Display for PackedSeq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seq: String = (0..self.len).map(|i| self.get(i) as char).collect();
+        write!(f, "{}", seq)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for seq in ["", "A", "ACG", "ACGT", "ACGTA", "TTGCAACGTAGCTAGGCAT"] {
+            let packed = PackedSeq::from_str(seq).unwrap();
+            assert_eq!(packed.len(), seq.len());
+            assert_eq!(packed.is_empty(), seq.is_empty());
+            assert_eq!(packed.to_string(), seq);
+            for (i, base) in seq.bytes().enumerate() {
+                assert_eq!(packed.get(i), base);
+            }
+        }
+        assert_eq!(PackedSeq::from_str("acgt").unwrap().to_string(), "ACGT");
+    }
+
+    #[test]
+    fn test_from_str_invalid() {
+        assert!(PackedSeq::from_str("ACGN").is_err());
+        assert!(PackedSeq::from_str("AC-T").is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_out_of_bounds() {
+        PackedSeq::from_str("ACGTA").unwrap().get(5);
+    }
+}
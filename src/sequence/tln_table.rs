@@ -2,16 +2,67 @@ use std::collections::HashMap;
 
 use lazy_static::lazy_static;
 
+use crate::error::{ProleError, ProleResult};
+
 /// Specify the translation table for encoding.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum TranslationTable {
-    T11
+    /// The standard code.
+    T1,
+    /// The bacterial, archaeal and plant plastid code.
+    T11,
 }
 
 
 impl TranslationTable {
+    /// Return the [TranslationTable] for a table number (e.g. `11`) or descriptive name
+    /// (e.g. `standard`, `bacterial`, `archaeal`), case-insensitive.
+    ///
+    /// ```
+    /// use prole::sequence::tln_table::TranslationTable;
+    ///
+    /// assert_eq!(TranslationTable::from_name("Standard").unwrap(), TranslationTable::T1);
+    /// assert_eq!(TranslationTable::from_name("11").unwrap(), TranslationTable::T11);
+    /// assert!(TranslationTable::from_name("vertebrate mitochondrial").is_err());
+    /// ```
+    pub fn from_name(name: &str) -> ProleResult<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "1" | "standard" => Ok(TranslationTable::T1),
+            "11" | "bacterial" | "archaeal" | "plastid" | "bacterial, archaeal and plant plastid" => Ok(TranslationTable::T11),
+            _ => Err(ProleError::Exit(format!("Unknown translation table: {}", name))),
+        }
+    }
+
     pub fn translate(&self, codon: &str) -> &char {
+        self.try_translate_ref(codon).unwrap()
+    }
+
+    /// Translate a codon, returning [None] if it is not in the table (e.g. it contains an `N`).
+    pub fn try_translate(&self, codon: &str) -> Option<char> {
+        self.try_translate_ref(codon).copied()
+    }
+
+    /// Returns true if the codon is a valid start codon for this table.
+    ///
+    /// Tables 1 and 11 encode the same amino acids, and differ only in their start codons.
+    ///
+    /// ```
+    /// use prole::sequence::tln_table::TranslationTable;
+    ///
+    /// assert!(TranslationTable::T11.is_start_codon("GTG"));
+    /// assert!(!TranslationTable::T1.is_start_codon("GTG"));
+    /// ```
+    pub fn is_start_codon(&self, codon: &str) -> bool {
+        match self {
+            TranslationTable::T1 => matches!(codon, "ATG" | "CTG" | "TTG"),
+            TranslationTable::T11 => matches!(codon, "ATG" | "GTG" | "TTG" | "CTG" | "ATT" | "ATC" | "ATA"),
+        }
+    }
+
+    fn try_translate_ref(&self, codon: &str) -> Option<&char> {
         match self {
-            TranslationTable::T11 => TLN_TABLE_11.get(codon).unwrap(),
+            // Both tables share the same amino acid (and stop codon) encoding
+            TranslationTable::T1 | TranslationTable::T11 => TLN_TABLE_11.get(codon),
         }
     }
 }
@@ -89,4 +140,33 @@ lazy_static! {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use crate::sequence::translate::translate_sequence;
+
+    use super::*;
+
+    #[test]
+    fn test_table_1_vs_11() {
+        let dna = "ATGGTGTGAATAATTTTGCTGTAGTAA";
+        let t1 = translate_sequence(dna, TranslationTable::T1);
+        assert_eq!(t1, "MV-IILL--");
+        assert_eq!(t1, translate_sequence(dna, TranslationTable::T11));
 
+        let starts_1: Vec<&str> = ["ATG", "GTG", "ATA", "ATT", "TTG", "CTG"].into_iter().filter(|c| TranslationTable::T1.is_start_codon(c)).collect();
+        let starts_11: Vec<&str> = ["ATG", "GTG", "ATA", "ATT", "TTG", "CTG"].into_iter().filter(|c| TranslationTable::T11.is_start_codon(c)).collect();
+        assert_eq!(starts_1, vec!["ATG", "TTG", "CTG"]);
+        assert_eq!(starts_11, vec!["ATG", "GTG", "ATA", "ATT", "TTG", "CTG"]);
+        assert!(!TranslationTable::T11.is_start_codon("TGA"));
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(TranslationTable::from_name("1").unwrap(), TranslationTable::T1);
+        assert_eq!(TranslationTable::from_name(" STANDARD ").unwrap(), TranslationTable::T1);
+        assert_eq!(TranslationTable::from_name("bacterial").unwrap(), TranslationTable::T11);
+        assert_eq!(TranslationTable::from_name("Archaeal").unwrap(), TranslationTable::T11);
+        assert!(TranslationTable::from_name("2").is_err());
+        assert!(TranslationTable::from_name("").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+/// Sequences with fewer than this many (ACGT) bases use the Wallace rule, otherwise the
+/// nearest-neighbour method is used, see [melting_temp].
+pub const WALLACE_MAX_LEN: usize = 13;
+
+/// The monovalent cation (Na+) concentration (M) assumed by the nearest-neighbour method.
+pub const NA_CONC: f64 = 0.05;
+
+/// The total oligo strand concentration (M) assumed by the nearest-neighbour method.
+pub const OLIGO_CONC: f64 = 250e-9;
+
+/// The gas constant (cal/K/mol).
+const R: f64 = 1.987;
+
+/// Returns the nearest-neighbour enthalpy (kcal/mol) and entropy (cal/K/mol) for a
+/// dinucleotide, using the unified parameters of SantaLucia (1998).
+fn nn_params(a: u8, b: u8) -> (f64, f64) {
+    match (a, b) {
+        (b'A', b'A') | (b'T', b'T') => (-7.9, -22.2),
+        (b'A', b'T') => (-7.2, -20.4),
+        (b'T', b'A') => (-7.2, -21.3),
+        (b'C', b'A') | (b'T', b'G') => (-8.5, -22.7),
+        (b'G', b'T') | (b'A', b'C') => (-8.4, -22.4),
+        (b'C', b'T') | (b'A', b'G') => (-7.8, -21.0),
+        (b'G', b'A') | (b'T', b'C') => (-8.2, -22.2),
+        (b'C', b'G') => (-10.6, -27.2),
+        (b'G', b'C') => (-9.8, -24.4),
+        _ => (-8.0, -19.9), // GG/CC
+    }
+}
+
+/// Returns the initiation enthalpy (kcal/mol) and entropy (cal/K/mol) for a terminal base.
+fn init_params(base: u8) -> (f64, f64) {
+    match base {
+        b'G' | b'C' => (0.1, -2.8),
+        _ => (2.3, 4.1),
+    }
+}
+
+/// Estimate the melting temperature (°C) of a DNA oligo (case-insensitive).
+///
+/// * Fewer than 14 bases: the Wallace rule, `4 * (G + C) + 2 * (A + T)`.
+/// * Otherwise: the nearest-neighbour method using the unified parameters of SantaLucia (1998),
+///   assuming a non-self-complementary oligo at [OLIGO_CONC] with [NA_CONC] Na+.
+///
+/// Any non-ACGT character (e.g. `N` or a gap) is removed before the estimate is made, therefore
+/// the bases either side are treated as adjacent and the length used to select the method is the
+/// number of ACGT bases. An empty sequence has a melting temperature of 0.
+///
+/// ```
+/// use prole::sequence::tm::melting_temp;
+///
+/// assert_eq!(melting_temp("ACGTACGT"), 24.0);
+/// assert_eq!(melting_temp("ACGTNNACGT"), melting_temp("ACGTACGT"));
+/// ```
+pub fn melting_temp(seq: &str) -> f64 {
+    let bases: Vec<u8> = seq.bytes()
+        .map(|base| base.to_ascii_uppercase())
+        .filter(|base| matches!(base, b'A' | b'C' | b'G' | b'T'))
+        .collect();
+    if bases.is_empty() {
+        return 0.0;
+    }
+
+    if bases.len() <= WALLACE_MAX_LEN {
+        let gc = bases.iter().filter(|&&base| base == b'G' || base == b'C').count();
+        let at = bases.len() - gc;
+        return (4 * gc + 2 * at) as f64;
+    }
+
+    let (mut dh, mut ds) = (0.0, 0.0);
+    for base in [bases[0], bases[bases.len() - 1]] {
+        let (h, s) = init_params(base);
+        dh += h;
+        ds += s;
+    }
+    for pair in bases.windows(2) {
+        let (h, s) = nn_params(pair[0], pair[1]);
+        dh += h;
+        ds += s;
+    }
+    ds += 0.368 * (bases.len() - 1) as f64 * NA_CONC.ln();
+    1000.0 * dh / (ds + R * (OLIGO_CONC / 4.0).ln()) - 273.15
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_melting_temp_wallace() {
+        assert_eq!(melting_temp(""), 0.0);
+        assert_eq!(melting_temp("NNN--"), 0.0);
+        assert_eq!(melting_temp("AAAA"), 8.0);
+        assert_eq!(melting_temp("gggg"), 16.0);
+        assert_eq!(melting_temp("ACGTACGTACGTA"), 38.0);
+    }
+
+    #[test]
+    fn test_melting_temp_nearest_neighbour() {
+        // Calculated by hand from the unified parameters of SantaLucia (1998)
+        let tm = melting_temp("AGCGGATAACAATTTCACACAGGA");
+        assert!((tm - 56.72).abs() < 0.1, "{}", tm);
+
+        // GC rich sequences melt at a higher temperature
+        assert!(melting_temp("GCGCGCGCGCGCGCGCGC") > melting_temp("ATATATATATATATATAT"));
+
+        // Case and non-ACGT characters are ignored
+        assert_eq!(melting_temp("agcggataacaatttcacacagga"), tm);
+        assert_eq!(melting_temp("AGCGGATAAC-AATTTCNACACAGGA"), tm);
+
+        // The strand does not matter
+        let tm_rev = melting_temp("TCCTGTGTGAAATTGTTATCCGCT");
+        assert!((tm - tm_rev).abs() < 1e-9);
+    }
+}
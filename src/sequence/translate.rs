@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+
+use bio::alphabets::dna::revcomp;
+
+use crate::error::{ProleError, ProleResult};
 use crate::sequence::tln_table::TranslationTable;
 
 /// Translate a nucleotide sequence into the corresponding amino acid sequence.
@@ -11,6 +16,114 @@ pub fn translate_sequence(seq: &str, table: TranslationTable) -> String {
     prot
 }
 
+/// Translate a single codon (case-insensitive), returning `X` if it is not in `table` (e.g. it
+/// contains an `N`, or is not valid UTF-8).
+fn translate_codon(codon: &[u8], table: TranslationTable) -> char {
+    std::str::from_utf8(&codon.to_ascii_uppercase()).ok()
+        .and_then(|codon| table.try_translate(codon))
+        .unwrap_or('X')
+}
+
+/// Translate the bytes of a nucleotide sequence starting at the offset `frame`, see
+/// [translate_sequence_frame].
+fn translate_bytes_frame(seq: &[u8], table: TranslationTable, frame: u8) -> String {
+    seq.get(frame as usize..).unwrap_or_default()
+        .chunks_exact(3)
+        .map(|codon| translate_codon(codon, table))
+        .collect()
+}
+
+/// Translate a nucleotide sequence into the corresponding amino acid sequence, starting at the
+/// offset `frame` (0, 1, or 2). Any incomplete codon at the end of the sequence is ignored.
+///
+/// Codons are translated case-insensitively, and any codon not in `table` (e.g. one containing
+/// an ambiguous base such as `N`) is translated as `X`.
+///
+/// ```
+/// use prole::sequence::tln_table::TranslationTable;
+/// use prole::sequence::translate::translate_sequence_frame;
+///
+/// assert_eq!(translate_sequence_frame("AATGAAATG", TranslationTable::T11, 1).unwrap(), "MK");
+/// assert_eq!(translate_sequence_frame("atgNNNaaa", TranslationTable::T11, 0).unwrap(), "MXK");
+/// assert!(translate_sequence_frame("AATGAAATG", TranslationTable::T11, 3).is_err());
+/// ```
+pub fn translate_sequence_frame(seq: &str, table: TranslationTable, frame: u8) -> ProleResult<String> {
+    if frame > 2 {
+        return Err(ProleError::Exit(format!("Invalid frame (expected 0, 1, or 2): {}", frame)));
+    }
+    Ok(translate_bytes_frame(seq.as_bytes(), table, frame))
+}
+
+/// Translate `seq` in all six frames and return the frame (0, 1, or 2), strand (`true` if
+/// forward), and protein sequence with the fewest internal stop codons (a trailing stop is
+/// not counted). Ties are resolved in favour of lower frames, and the forward strand.
+///
+/// Codons that are not in `table` (e.g. those containing an `N`) are translated as `X` (see
+/// [translate_sequence_frame]), and are not counted as stop codons.
+///
+/// ```
+/// use prole::sequence::tln_table::TranslationTable;
+/// use prole::sequence::translate::best_frame;
+///
+/// assert_eq!(best_frame("TGATTAATCAGTCA", TranslationTable::T11), (2, true, "INQS".to_string()));
+/// ```
+pub fn best_frame(seq: &str, table: TranslationTable) -> (u8, bool, String) {
+    let rev = revcomp(seq.as_bytes());
+    let mut best: Option<(u8, bool, String, usize)> = None;
+    for (forward, strand_seq) in [(true, seq.as_bytes()), (false, rev.as_slice())] {
+        for frame in 0..3 {
+            let prot = translate_bytes_frame(strand_seq, table, frame);
+            let n_stops = prot.trim_end_matches('-').matches('-').count();
+            if best.as_ref().map_or(true, |(_, _, _, best_stops)| n_stops < *best_stops) {
+                best = Some((frame, forward, prot, n_stops));
+            }
+        }
+    }
+    let (frame, forward, prot, _) = best.unwrap_or_default();
+    (frame, forward, prot)
+}
+
+
+/// Back-translate a protein sequence into a nucleotide sequence, using the first codon (in
+/// lexicographic order) that encodes each residue in `table`.
+///
+/// Alignment gaps (`-`) are emitted as `---`, stop codons (`*`) as the first stop codon, and
+/// `X` (or any residue not encoded by the table) as `NNN`.
+///
+/// This is ambiguous (synonymous codons are indistinguishable), therefore it is intended for
+/// scaffolding (e.g. codon-aware alignment), and will not recover the original sequence.
+///
+/// ```
+/// use prole::sequence::tln_table::TranslationTable;
+/// use prole::sequence::translate::back_translate;
+///
+/// assert_eq!(back_translate("MK-X*", TranslationTable::T11), "ATGAAA---NNNTAA");
+/// ```
+pub fn back_translate(protein: &str, table: TranslationTable) -> String {
+    const BASES: [char; 4] = ['A', 'C', 'G', 'T'];
+    let mut codons: HashMap<char, String> = HashMap::new();
+    for a in BASES {
+        for b in BASES {
+            for c in BASES {
+                let codon: String = [a, b, c].iter().collect();
+                if let Some(aa) = table.try_translate(&codon) {
+                    // The table encodes stop codons as '-', which would be ambiguous with a gap
+                    let aa = if aa == '-' { '*' } else { aa };
+                    codons.entry(aa).or_insert(codon);
+                }
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(protein.len() * 3);
+    for aa in protein.chars() {
+        match aa {
+            '-' => out.push_str("---"),
+            _ => out.push_str(codons.get(&aa.to_ascii_uppercase()).map_or("NNN", |codon| codon.as_str())),
+        }
+    }
+    out
+}
 
 #[cfg(test)]
 mod tests {
@@ -21,5 +134,46 @@ mod tests {
         let dna = "AAAAACAAGAATACAACCACGACTAGAAGCAGGAGTATAATCATGATTCAACACCAGCATCCACCCCCGCCTCGACGCCGGCGTCTACTCCTGCTTGAAGACGAGGATGCAGCCGCGGCTGGAGGCGGGGGTGTAGTCGTGGTTTAATACTAGTATTCATCCTCGTCTTGATGCTGGTGTTTATTCTTGTTT";
         let prot_expected = "KNKNTTTTRSRSIIMIQHQHPPPPRRRRLLLLEDEDAAAAGGGGVVVV-Y-YSSSS-CWCLFLF";
         let prot = translate_sequence(dna, TranslationTable::T11);
+        assert_eq!(prot, prot_expected);
+    }
+
+    #[test]
+    fn test_translate_sequence_frame() {
+        let dna = "CATGAAACCCTAA";
+        assert_eq!(translate_sequence_frame(dna, TranslationTable::T11, 0).unwrap(), "HETL");
+        assert_eq!(translate_sequence_frame(dna, TranslationTable::T11, 1).unwrap(), "MKP-");
+        assert_eq!(translate_sequence_frame(dna, TranslationTable::T11, 2).unwrap(), "-NP");
+        assert_eq!(translate_sequence_frame("AT", TranslationTable::T11, 2).unwrap(), "");
+        assert!(translate_sequence_frame(dna, TranslationTable::T11, 3).is_err());
+
+        // Lowercase, ambiguous, and non-ASCII input does not panic
+        assert_eq!(translate_sequence_frame("ATGNNNAAA", TranslationTable::T11, 0).unwrap(), "MXK");
+        assert_eq!(translate_sequence_frame("atgaaa", TranslationTable::T11, 0).unwrap(), "MK");
+        assert_eq!(translate_sequence_frame("AéTGAAA", TranslationTable::T11, 1).unwrap(), "XE");
+        assert_eq!(translate_sequence_frame("Aé", TranslationTable::T11, 2).unwrap(), "");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_best_frame() {
+        // Only a single frame is free of internal stops
+        assert_eq!(best_frame("TGATTAATCAGTCA", TranslationTable::T11), (2, true, "INQS".to_string()));
+        assert_eq!(best_frame("TGATTAAGTAATTA", TranslationTable::T11), (1, false, "NYLI".to_string()));
+        // A trailing stop is not counted
+        assert_eq!(best_frame("ATGAAATAA", TranslationTable::T11), (0, true, "MK-".to_string()));
+        // No stops in any frame, prefer frame 0 forward
+        assert_eq!(best_frame("AAAAAA", TranslationTable::T11), (0, true, "KK".to_string()));
+        assert_eq!(best_frame("", TranslationTable::T11), (0, true, "".to_string()));
+        // Ambiguous bases are translated as X
+        assert_eq!(best_frame("ATGNNNAAAT", TranslationTable::T11), (0, true, "MXK".to_string()));
+        assert_eq!(best_frame("tgattaatcagtca", TranslationTable::T11), (2, true, "INQS".to_string()));
+    }
+
+    #[test]
+    fn test_back_translate() {
+        let prot = "MKPW*X-l";
+        let dna = back_translate(prot, TranslationTable::T11);
+        assert_eq!(dna, "ATGAAACCATGGTAANNN---CTA");
+        assert_eq!(translate_sequence(&dna.replace("NNN", "AAA").replace("---", "AAA"), TranslationTable::T11), "MKPW-KKL");
+        assert_eq!(back_translate("", TranslationTable::T11), "");
+    }
+}
@@ -0,0 +1,72 @@
+/// The units used when formatting a number of bytes (base-1024).
+const BYTE_UNITS: [&str; 7] = ["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+
+/// Format a number of bytes in a human-readable form using base-1024 units and one decimal place.
+///
+/// ```
+/// use prole::util::fmt::human_bytes;
+///
+/// assert_eq!(human_bytes(1023), "1023 B");
+/// assert_eq!(human_bytes(1024), "1.0 KB");
+/// assert_eq!(human_bytes(1288490189), "1.2 GB");
+/// ```
+pub fn human_bytes(n: u64) -> String {
+    human_bytes_with_precision(n, 1)
+}
+
+/// Format a number of bytes in a human-readable form using base-1024 units and `precision`
+/// decimal places. Values below 1 KB are always reported as a whole number of bytes.
+///
+/// ```
+/// use prole::util::fmt::human_bytes_with_precision;
+///
+/// assert_eq!(human_bytes_with_precision(361758720, 0), "345 MB");
+/// assert_eq!(human_bytes_with_precision(1536, 2), "1.50 KB");
+/// ```
+pub fn human_bytes_with_precision(n: u64, precision: usize) -> String {
+    if n < 1024 {
+        return format!("{} B", n);
+    }
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    // Rounding may push the value up to the next unit (e.g. 1023.99 KB -> 1024.0 KB)
+    let scale = 10_f64.powi(precision as i32);
+    if (value * scale).round() / scale >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.*} {}", precision, value, BYTE_UNITS[unit])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(1023), "1023 B");
+        assert_eq!(human_bytes(1024), "1.0 KB");
+        assert_eq!(human_bytes(1536), "1.5 KB");
+        assert_eq!(human_bytes(1024 * 1024 - 1), "1.0 MB");
+        assert_eq!(human_bytes(1024 * 1024), "1.0 MB");
+        assert_eq!(human_bytes(345 * 1024 * 1024), "345.0 MB");
+        assert_eq!(human_bytes(1288490189), "1.2 GB");
+        assert_eq!(human_bytes(u64::MAX), "16.0 EB");
+    }
+
+    #[test]
+    fn test_human_bytes_with_precision() {
+        assert_eq!(human_bytes_with_precision(1023, 2), "1023 B");
+        assert_eq!(human_bytes_with_precision(1024, 0), "1 KB");
+        assert_eq!(human_bytes_with_precision(1024, 3), "1.000 KB");
+        assert_eq!(human_bytes_with_precision(1288490189, 2), "1.20 GB");
+        assert_eq!(human_bytes_with_precision(1024 * 1024 - 1, 3), "1023.999 KB");
+    }
+}
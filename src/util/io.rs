@@ -0,0 +1,245 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::error::{ProleError, ProleResult};
+
+/// Returns true if the [Path] has a `.gz` extension.
+///
+/// ```
+/// use std::path::Path;
+/// use prole::util::io::is_gz;
+///
+/// assert!(is_gz(Path::new("genome.fna.gz")));
+/// assert!(!is_gz(Path::new("genome.fna")));
+/// ```
+pub fn is_gz(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext == "gz")
+}
+
+/// Returns true if `bytes` starts with a bzip2 stream header followed by the magic of either a
+/// compressed block or the end of the stream (i.e. an empty stream).
+fn is_bzip2(bytes: &[u8]) -> bool {
+    const BLOCK_MAGIC: [u8; 6] = [0x31, 0x41, 0x59, 0x26, 0x53, 0x59];
+    const EOS_MAGIC: [u8; 6] = [0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+    bytes.len() >= 10
+        && bytes.starts_with(b"BZh")
+        && (b'1'..=b'9').contains(&bytes[3])
+        && (bytes[4..10] == BLOCK_MAGIC || bytes[4..10] == EOS_MAGIC)
+}
+
+/// The compression format of a file, as detected by [Compression::detect].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    /// Requires the `bzip2` feature to decompress.
+    Bzip2,
+    /// Requires the `xz` feature to decompress.
+    Xz,
+}
+
+impl Compression {
+    /// Detect the compression format from the magic bytes at the start of a file.
+    ///
+    /// As the bzip2 magic (`BZh`) is printable, it is only detected if followed by a block size
+    /// (`1` to `9`) and the magic of a block or the end of the stream, so that a plain text file
+    /// starting with `BZh` is not mistaken for bzip2.
+    ///
+    /// ```
+    /// use prole::util::io::Compression;
+    ///
+    /// assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+    /// assert_eq!(Compression::detect(b"BZh91AY&SY"), Compression::Bzip2);
+    /// assert_eq!(Compression::detect(b"BZh9 is plain text"), Compression::None);
+    /// assert_eq!(Compression::detect(b">contig_1"), Compression::None);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Self::Gzip
+        } else if is_bzip2(bytes) {
+            Self::Bzip2
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Self::Xz
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Open the file at [Path] for reading, decompressing it if it starts with the gzip, bzip2, or
+/// xz magic bytes (see [Compression::detect]). The extension is not considered.
+///
+/// An error is returned for a bzip2 or xz file if the `bzip2` or `xz` feature is not enabled.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use prole::util::io::open_reader;
+///
+/// let reader = open_reader(Path::new("/path/to/file.gz")).unwrap();
+/// ```
+pub fn open_reader(path: &Path) -> ProleResult<BufReader<Box<dyn Read>>> {
+    let mut file = BufReader::new(File::open(path).map_err(ProleError::IoError)?);
+    let compression = Compression::detect(file.fill_buf().map_err(ProleError::IoError)?);
+    let inner: Box<dyn Read> = match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(GzDecoder::new(file)),
+        #[cfg(feature = "bzip2")]
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        #[allow(unreachable_patterns)]
+        _ => {
+            let feature = if compression == Compression::Bzip2 { "bzip2" } else { "xz" };
+            return Err(ProleError::Exit(format!("The {} feature is required to read: {}", feature, path.display())));
+        }
+    };
+    Ok(BufReader::new(inner))
+}
+
+/// A buffered file writer which compresses the output if the extension is `.gz`.
+///
+/// [FileWriter::finish] should be called once writing is complete so that any errors
+/// encountered while flushing (or writing the gzip trailer) are reported.
+pub enum FileWriter {
+    Plain(BufWriter<File>),
+    Gz(GzEncoder<BufWriter<File>>),
+}
+
+impl FileWriter {
+    /// Create (or truncate) the file at [Path] for writing.
+    ///
+    /// ```no_run
+    /// use std::io::Write;
+    /// use std::path::Path;
+    /// use prole::util::io::FileWriter;
+    ///
+    /// let mut writer = FileWriter::create(Path::new("/path/to/file.gz")).unwrap();
+    /// writeln!(writer, "hello").unwrap();
+    /// writer.finish().unwrap();
+    /// ```
+    pub fn create(path: &Path) -> ProleResult<Self> {
+        let file = BufWriter::new(File::create(path).map_err(ProleError::IoError)?);
+        if is_gz(path) {
+            Ok(Self::Gz(GzEncoder::new(file, flate2::Compression::default())))
+        } else {
+            Ok(Self::Plain(file))
+        }
+    }
+
+    /// Flush all buffered content to disk.
+    pub fn finish(self) -> ProleResult<()> {
+        let mut file = match self {
+            Self::Plain(file) => file,
+            Self::Gz(gz) => gz.finish().map_err(ProleError::IoError)?,
+        };
+        file.flush().map_err(ProleError::IoError)
+    }
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            Self::Gz(gz) => gz.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            Self::Gz(gz) => gz.flush(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempdir().unwrap();
+        for name in ["file.txt", "file.txt.gz"] {
+            let path = dir.path().join(name);
+            let mut writer = FileWriter::create(&path).unwrap();
+            writeln!(writer, "line 1").unwrap();
+            writeln!(writer, "line 2").unwrap();
+            writer.finish().unwrap();
+
+            let lines: Vec<String> = open_reader(&path).unwrap().lines().map(|x| x.unwrap()).collect();
+            assert_eq!(lines, vec!["line 1", "line 2"]);
+        }
+    }
+
+    #[test]
+    fn test_gz_is_compressed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.gz");
+        let mut writer = FileWriter::create(&path).unwrap();
+        writeln!(writer, "line 1").unwrap();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+    }
+
+    #[test]
+    fn test_open_reader_detects_compression() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let mut writer = GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+        writeln!(writer, "line 1").unwrap();
+        writer.finish().unwrap();
+
+        let lines: Vec<String> = open_reader(&path).unwrap().lines().map(|x| x.unwrap()).collect();
+        assert_eq!(lines, vec!["line 1"]);
+
+        let path = dir.path().join("file.bz2");
+        std::fs::write(&path, b"BZh9\x17\x72\x45\x38\x50\x90\x00\x00\x00\x00").unwrap();
+        assert_eq!(open_reader(&path).is_ok(), cfg!(feature = "bzip2"));
+
+        let path = dir.path().join("bzh.txt");
+        std::fs::write(&path, "BZh9 is not compressed\n").unwrap();
+        let lines: Vec<String> = open_reader(&path).unwrap().lines().map(|x| x.unwrap()).collect();
+        assert_eq!(lines, vec!["BZh9 is not compressed"]);
+
+        let path = dir.path().join("empty.txt");
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(open_reader(&path).unwrap().lines().count(), 0);
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_open_reader_bzip2() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt.bz2");
+        let mut writer = bzip2::write::BzEncoder::new(File::create(&path).unwrap(), bzip2::Compression::default());
+        writeln!(writer, "line 1").unwrap();
+        writer.finish().unwrap();
+
+        let lines: Vec<String> = open_reader(&path).unwrap().lines().map(|x| x.unwrap()).collect();
+        assert_eq!(lines, vec!["line 1"]);
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_open_reader_xz() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt.xz");
+        let mut writer = xz2::write::XzEncoder::new(File::create(&path).unwrap(), 6);
+        writeln!(writer, "line 1").unwrap();
+        writer.finish().unwrap();
+
+        let lines: Vec<String> = open_reader(&path).unwrap().lines().map(|x| x.unwrap()).collect();
+        assert_eq!(lines, vec!["line 1"]);
+    }
+}
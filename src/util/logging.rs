@@ -1,12 +1,36 @@
 use std::env;
 use std::io::Write;
 
+use chrono::format::{Item, StrftimeItems};
 use chrono::Local;
 use env_logger::Builder;
 use env_logger::fmt::Color;
 use log::Level;
 use log::LevelFilter;
 
+use crate::error::{ProleError, ProleResult};
+
+/// Options for [init_logger_opts].
+#[derive(Debug, Clone)]
+pub struct LoggerOpts {
+    /// The level used if `RUST_LOG` is not set.
+    pub level: LevelFilter,
+    /// The [chrono::format::strftime] format of the timestamp prefixing each message.
+    pub timestamp_fmt: String,
+    /// If true, the level is written in bold and coloured using ANSI codes.
+    pub color: bool,
+}
+
+impl Default for LoggerOpts {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            timestamp_fmt: "%Y-%m-%d %H:%M:%S".to_string(),
+            color: true,
+        }
+    }
+}
+
 /// Initialises an env_logger with the specified [LevelFilter].
 ///
 /// ```
@@ -16,13 +40,37 @@ use log::LevelFilter;
 /// init_logger(LevelFilter::Info);
 /// ```
 pub fn init_logger(level: LevelFilter) {
-    // Initialise the logger at the default level of info
+    init_logger_opts(LoggerOpts { level, ..Default::default() }).unwrap();
+}
+
+/// Initialises an env_logger with the specified [LoggerOpts]. An error is returned if the
+/// `timestamp_fmt` is not a valid [chrono::format::strftime] format.
+///
+/// ```
+/// use log::LevelFilter;
+/// use prole::util::logging::{init_logger_opts, LoggerOpts};
+///
+/// assert!(init_logger_opts(LoggerOpts { timestamp_fmt: "%Q".to_string(), ..Default::default() }).is_err());
+/// init_logger_opts(LoggerOpts { level: LevelFilter::Info, timestamp_fmt: "%H:%M:%S".to_string(), color: false }).unwrap();
+/// ```
+pub fn init_logger_opts(opts: LoggerOpts) -> ProleResult<()> {
+    if StrftimeItems::new(&opts.timestamp_fmt).any(|item| item == Item::Error) {
+        return Err(ProleError::Exit(format!("Invalid timestamp format: {}", opts.timestamp_fmt)));
+    }
+
+    // Initialise the logger at the requested level, unless RUST_LOG is set
     if env::var("RUST_LOG").is_err() {
-        env::set_var("RUST_LOG", level.as_str())
+        env::set_var("RUST_LOG", opts.level.as_str())
     }
+    let LoggerOpts { timestamp_fmt, color, .. } = opts;
     // env_logger::init();
     Builder::from_default_env()
-        .format(|buf, record| {
+        .format(move |buf, record| {
+            let timestamp = Local::now().format(&timestamp_fmt);
+            if !color {
+                return writeln!(buf, "[{}] {} - {}", timestamp, record.level(), record.args());
+            }
+
             let color = match record.level() {
                 Level::Error => Color::Red,
                 Level::Warn => Color::Yellow,
@@ -35,11 +83,24 @@ pub fn init_logger(level: LevelFilter) {
 
             writeln!(buf,
                      "[{}] {} - {}",
-                     Local::now().format("%Y-%m-%d %H:%M:%S"),
+                     timestamp,
                      style.value(record.level()),
                      record.args()
             )
         })
-        .filter(None, LevelFilter::Info)
         .init();
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_logger_opts_level() {
+        env::remove_var("RUST_LOG");
+        init_logger_opts(LoggerOpts { level: LevelFilter::Debug, ..Default::default() }).unwrap();
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+    }
 }
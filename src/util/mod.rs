@@ -1 +1,5 @@
-pub mod logging;
\ No newline at end of file
+pub mod logging;
+pub mod timer;
+pub mod fmt;
+pub mod io;
+pub mod parse;
@@ -0,0 +1,138 @@
+use std::io::{BufRead, BufReader};
+
+use crate::error::{ProleError, ProleResult};
+
+/// A record that is parsed from a single line of text.
+pub trait FromLine: Sized {
+    /// Parse a single line into a record.
+    fn from_line(line: &str) -> ProleResult<Self>;
+
+    /// Returns true if the line does not contain a record and should be skipped,
+    /// by default this is any empty line, or comment (starting with `#`).
+    fn is_skipped(line: &str) -> bool {
+        line.is_empty() || line.starts_with('#')
+    }
+}
+
+/// Returns an [Iterator] over the lines of a reader, any trailing `\r` (i.e. a Windows CRLF
+/// line ending) is removed from each line.
+///
+/// ```
+/// use std::io::BufReader;
+/// use prole::util::parse::read_lines;
+///
+/// let lines: Vec<String> = read_lines(BufReader::new("a\tb\r\nc\n".as_bytes())).map(|x| x.unwrap()).collect();
+/// assert_eq!(lines, vec!["a\tb", "c"]);
+/// ```
+pub fn read_lines<R: BufRead>(buf: R) -> impl Iterator<Item=ProleResult<String>> {
+    buf.lines().map(|line| {
+        let mut line = line.map_err(ProleError::IoError)?;
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        Ok(line)
+    })
+}
+
+/// Split a line into the columns separated by `delim`. A column may be enclosed in double quotes
+/// to include the delimiter, where any double quote within it is escaped as `""` (i.e. as
+/// written by most CSV writers). Quotes that do not start a column are kept as-is.
+///
+/// ```
+/// use prole::util::parse::split_quoted;
+///
+/// let columns = split_quoted("a,\"b, \"\"c\"\"\",d\"e", ',').unwrap();
+/// assert_eq!(columns, vec!["a", "b, \"c\"", "d\"e"]);
+/// ```
+pub fn split_quoted(line: &str, delim: char) -> ProleResult<Vec<String>> {
+    let mut out = Vec::new();
+    let mut column = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    let mut column_start = true;
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                column.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                column.push('"');
+            } else {
+                quoted = false;
+            }
+        } else if c == delim {
+            out.push(std::mem::take(&mut column));
+            column_start = true;
+            continue;
+        } else if c == '"' && column_start {
+            quoted = true;
+        } else {
+            column.push(c);
+        }
+        column_start = false;
+    }
+    if quoted {
+        return Err(ProleError::Exit(format!("Unterminated quote: {}", line)));
+    }
+    out.push(column);
+    Ok(out)
+}
+
+/// Parse each line of a [BufReader] into a record, lines are skipped as per [FromLine::is_skipped].
+///
+/// ```
+/// use std::io::BufReader;
+/// use prole::hmm::pypfam_file::PyPfamHit;
+/// use prole::util::parse::parse_lines;
+///
+/// let content = "# comment\n\nCAKWUX010000001.1_1       1    263      1    265 PF02896.19  PEP-utilizers_C   Domain    72   292   294    252.7   5.5e-76   1 CL0151\n";
+/// let hits: Vec<PyPfamHit> = parse_lines(BufReader::new(content.as_bytes())).unwrap();
+/// assert_eq!(hits.len(), 1);
+/// ```
+pub fn parse_lines<T: FromLine, R: std::io::Read>(buf: BufReader<R>) -> ProleResult<Vec<T>> {
+    let mut out = vec![];
+    for line in read_lines(buf) {
+        let line = line?;
+        if T::is_skipped(&line) {
+            continue;
+        }
+        out.push(T::from_line(&line)?);
+    }
+    Ok(out)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Number(u32);
+
+    impl FromLine for Number {
+        fn from_line(line: &str) -> ProleResult<Self> {
+            Ok(Number(line.trim().parse().map_err(ProleError::ParseIntError)?))
+        }
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let numbers: Vec<Number> = parse_lines(BufReader::new("# header\n1\n\n2\n 3\n".as_bytes())).unwrap();
+        assert_eq!(numbers, vec![Number(1), Number(2), Number(3)]);
+
+        let numbers: ProleResult<Vec<Number>> = parse_lines(BufReader::new("1\nx\n".as_bytes()));
+        assert!(numbers.is_err());
+
+        let numbers: Vec<Number> = parse_lines(BufReader::new("# header\r\n1\r\n\r\n2\r\n".as_bytes())).unwrap();
+        assert_eq!(numbers, vec![Number(1), Number(2)]);
+    }
+
+    #[test]
+    fn test_split_quoted() {
+        assert_eq!(split_quoted("", ',').unwrap(), vec![""]);
+        assert_eq!(split_quoted("a,,b,", ',').unwrap(), vec!["a", "", "b", ""]);
+        assert_eq!(split_quoted("\"a,b\",\"\",\"c\"\"\"", ',').unwrap(), vec!["a,b", "", "c\""]);
+        assert_eq!(split_quoted("a\tb \"c\"", '\t').unwrap(), vec!["a", "b \"c\""]);
+        assert!(split_quoted("a,\"b,c", ',').is_err());
+    }
+}
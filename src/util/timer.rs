@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+/// Format a [Duration] in a human-readable form, e.g. `1h 2m 3s`, `1m 23s`, `45s`, or `120ms`.
+///
+/// ```
+/// use std::time::Duration;
+/// use prole::util::timer::format_duration;
+///
+/// assert_eq!(format_duration(Duration::from_secs(83)), "1m 23s");
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        return format!("{}ms", duration.as_millis());
+    }
+    let (hours, mins, secs) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, mins, secs)
+    } else if mins > 0 {
+        format!("{}m {}s", mins, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// A stopwatch used to log the time taken by long-running steps.
+///
+/// ```
+/// use prole::util::timer::Timer;
+///
+/// let timer = Timer::start();
+/// // ... do some work
+/// timer.log_elapsed("Loading genomes");
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Timer(Instant);
+
+impl Timer {
+    /// Start a new [Timer].
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+
+    /// Returns the time elapsed since the [Timer] was started.
+    pub fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+
+    /// Log the time elapsed since the [Timer] was started at debug level.
+    pub fn log_elapsed(&self, label: &str) {
+        debug!("{} took {}", label, format_duration(self.elapsed()));
+    }
+}
+
+/// A [Timer] that logs the elapsed time at debug level when it goes out of scope.
+///
+/// ```
+/// use prole::util::timer::ScopeTimer;
+///
+/// fn process() {
+///     let _timer = ScopeTimer::new("process");
+///     // ... do some work
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ScopeTimer {
+    label: String,
+    timer: Timer,
+}
+
+impl ScopeTimer {
+    /// Start a new [ScopeTimer] that will be reported using `label`.
+    pub fn new(label: &str) -> Self {
+        Self { label: label.to_string(), timer: Timer::start() }
+    }
+}
+
+impl Drop for ScopeTimer {
+    fn drop(&mut self) {
+        self.timer.log_elapsed(&self.label);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(0)), "0ms");
+        assert_eq!(format_duration(Duration::from_millis(120)), "120ms");
+        assert_eq!(format_duration(Duration::from_secs(1)), "1s");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::from_secs(60)), "1m 0s");
+        assert_eq!(format_duration(Duration::from_secs(83)), "1m 23s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h 2m 3s");
+    }
+
+    #[test]
+    fn test_timer_elapsed() {
+        let timer = Timer::start();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(timer.elapsed() >= Duration::from_millis(5));
+    }
+}